@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use proptest::prelude::Rng;
+use proptest::test_runner::{RngAlgorithm, TestRng};
+use std::collections::HashSet;
+use customs::answers::AnswerSet;
+use customs::declaration::{DeclarationForm, DeclarationGroup};
+
+const GROUP_COUNT: usize = 1_000_000;
+
+// Seeded the same way on every run, so the benchmark input never changes
+fn generate_groups() -> Vec<Vec<Vec<u8>>> {
+    let mut rng = TestRng::deterministic_rng(RngAlgorithm::ChaCha);
+    (0..GROUP_COUNT)
+        .map(|_| (0..1 + rng.next_u64() % 5)
+            .map(|_| (b'a'..=b'z').filter(|_| rng.next_u64().is_multiple_of(3)).collect())
+            .collect())
+        .collect()
+}
+
+fn hash_set_all(group: &[HashSet<u8>]) -> HashSet<u8> {
+    group[1..]
+        .iter()
+        .fold(group[0].clone(), |a, b| a.intersection(b).copied().collect())
+}
+
+fn hash_set_any(group: &[HashSet<u8>]) -> HashSet<u8> {
+    group.iter().fold(HashSet::new(), |a, b| a.union(b).copied().collect())
+}
+
+fn bench_answers(c: &mut Criterion) {
+    let raw = generate_groups();
+    let hash_sets: Vec<Vec<HashSet<u8>>> = raw
+        .iter()
+        .map(|group| group.iter().map(|form| form.iter().copied().collect()).collect())
+        .collect();
+    let bit_sets: Vec<DeclarationGroup> = raw
+        .iter()
+        .map(|group| DeclarationGroup {
+            data: group
+                .iter()
+                .map(|form| DeclarationForm { data: form.iter().copied().collect::<AnswerSet>() })
+                .collect()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("million_groups");
+    group.sample_size(10);
+    group.bench_function("hash_set_all", |b| b.iter(|| hash_sets.iter().map(|g| hash_set_all(g).len()).sum::<usize>()));
+    group.bench_function("bit_set_all", |b| b.iter(|| bit_sets.iter().map(|g| g.all_answers().len()).sum::<usize>()));
+    group.bench_function("hash_set_any", |b| b.iter(|| hash_sets.iter().map(|g| hash_set_any(g).len()).sum::<usize>()));
+    group.bench_function("bit_set_any", |b| b.iter(|| bit_sets.iter().map(|g| g.any_answers().len()).sum::<usize>()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_answers);
+criterion_main!(benches);
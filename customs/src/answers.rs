@@ -0,0 +1,111 @@
+use std::ops::{BitAnd, BitOr};
+use std::iter::FromIterator;
+
+// The questions on the form are 'a' through 'z', so one bit per question fits in a u32
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct AnswerSet(u32);
+
+pub const QUESTION_COUNT: usize = 26;
+
+pub fn is_question(b: u8) -> bool {
+    b.is_ascii_lowercase()
+}
+
+fn bit(question: u8) -> u32 {
+    1 << (question - b'a')
+}
+
+impl AnswerSet {
+    pub fn empty() -> AnswerSet {
+        AnswerSet(0)
+    }
+
+    pub fn full() -> AnswerSet {
+        AnswerSet((1 << QUESTION_COUNT) - 1)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn insert(&mut self, question: u8) {
+        assert!(is_question(question), "'{}' is not a question", question as char);
+        self.0 |= bit(question);
+    }
+
+    pub fn contains(&self, question: &u8) -> bool {
+        is_question(*question) && self.0 & bit(*question) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: &AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 & other.0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=u8> + '_ {
+        (b'a'..=b'z').filter(move |question| self.contains(question))
+    }
+}
+
+impl BitOr for AnswerSet {
+    type Output = AnswerSet;
+
+    fn bitor(self, rhs: AnswerSet) -> AnswerSet {
+        self.union(&rhs)
+    }
+}
+
+impl BitAnd for AnswerSet {
+    type Output = AnswerSet;
+
+    fn bitand(self, rhs: AnswerSet) -> AnswerSet {
+        self.intersection(&rhs)
+    }
+}
+
+impl FromIterator<u8> for AnswerSet {
+    fn from_iter<T: IntoIterator<Item=u8>>(iter: T) -> Self {
+        let mut set = AnswerSet::empty();
+        for question in iter {
+            set.insert(question);
+        }
+        set
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_operations() {
+        let a: AnswerSet = b"abc".iter().copied().collect();
+        let b: AnswerSet = b"cdz".iter().copied().collect();
+        assert_eq!(a.len(), 3);
+        assert!(a.contains(&b'a'));
+        assert!(!a.contains(&b'z'));
+        assert!(!a.contains(&b'A'));
+        assert_eq!((a | b).len(), 5);
+        assert_eq!((a & b).iter().collect::<Vec<u8>>(), vec![b'c']);
+        assert_eq!(AnswerSet::full().len(), QUESTION_COUNT);
+        assert!(AnswerSet::empty().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_non_question() {
+        AnswerSet::empty().insert(b'\r');
+    }
+}
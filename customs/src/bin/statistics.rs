@@ -0,0 +1,23 @@
+use clap::{Arg, App};
+use std::fs;
+use customs::declaration::parse_groups;
+use customs::statistics::Statistics;
+
+
+fn main() {
+    let args = App::new("Per-question statistics for customs declarations")
+        .arg(Arg::with_name("input-file").takes_value(true))
+        .arg(Arg::with_name("format").long("format").takes_value(true).possible_values(&["table", "json"]).default_value("table"))
+        .get_matches();
+    let groups = parse_groups(&fs::read_to_string(
+        args.value_of("input-file").unwrap()
+    )
+        .unwrap()
+    )
+        .unwrap();
+    let stats = Statistics::from_groups(&groups);
+    match args.value_of("format").unwrap() {
+        "json" => println!("{}", stats.to_json()),
+        _ => print!("{}", stats.to_table()),
+    }
+}
@@ -0,0 +1,258 @@
+use std::str::FromStr;
+use crate::answers::{AnswerSet, QUESTION_COUNT, is_question};
+
+
+// A single person's answers. An empty form is a person who answered no to everything.
+pub struct DeclarationForm {
+    pub data: AnswerSet,
+}
+
+
+// A group may hold no forms at all; such a group answered yes to nothing, so both its
+// `any_answers` and `all_answers` are empty.
+pub struct DeclarationGroup {
+    pub data: Vec<DeclarationForm>,
+}
+
+impl FromStr for DeclarationForm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((column, b)) = s.bytes().enumerate().find(|(_, b)| !is_question(*b)) {
+            return Err(format!("invalid answer {:?} at column {}, expected a-z", b as char, column + 1));
+        }
+        Ok(DeclarationForm {
+            data: s
+                .bytes()
+                .collect()
+        })
+    }
+}
+
+// One form per line. A blank line would separate groups in `parse_groups`, so one here is
+// an error rather than a person who answered nothing.
+impl FromStr for DeclarationGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(DeclarationGroup {
+            data: s
+                .lines()
+                .zip(1..)
+                .map(|(line, line_number)| match line {
+                    "" => Err(format!("line {}: blank line inside a group", line_number)),
+                    line => parse_form(line, line_number),
+                })
+                .collect::<Result<_, _>>()?
+        })
+    }
+}
+
+fn parse_form(line: &str, line_number: usize) -> Result<DeclarationForm, String> {
+    line.parse().map_err(|e| format!("line {}: {}", line_number, e))
+}
+
+impl DeclarationGroup {
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    // Questions anyone in the group answered yes to
+    pub fn any_answers(&self) -> AnswerSet {
+        self.data.iter().fold(AnswerSet::empty(), |a, b| a | b.data)
+    }
+
+    // Questions everyone in the group answered yes to
+    pub fn all_answers(&self) -> AnswerSet {
+        if self.data.is_empty() {
+            return AnswerSet::empty();
+        }
+        self.data.iter().fold(AnswerSet::full(), |a, b| a & b.data)
+    }
+
+    // How many people in the group answered yes to each question, indexed from 'a'
+    pub fn answer_counts(&self) -> [usize; QUESTION_COUNT] {
+        let mut counts = [0; QUESTION_COUNT];
+        for form in &self.data {
+            for answer in form.data.iter() {
+                counts[(answer - b'a') as usize] += 1;
+            }
+        }
+        counts
+    }
+}
+
+// Groups are separated by one or more blank lines. Leading and trailing blank lines are
+// ignored, so an input never produces an empty group. Since a blank line always ends a
+// group, a person who answered nothing cannot be written in the text format, and
+// `DeclarationGroup::from_str` rejects a blank line for the same reason.
+pub fn parse_groups(s: &str) -> Result<Vec<DeclarationGroup>, String> {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    for (line, line_number) in s.lines().zip(1..) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                groups.push(DeclarationGroup { data: current });
+                current = Vec::new();
+            }
+        } else {
+            current.push(parse_form(line, line_number)?);
+        }
+    }
+    if !current.is_empty() {
+        groups.push(DeclarationGroup { data: current });
+    }
+    Ok(groups)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_answer_counts() -> Result<(), String> {
+        let group = "ab\nac\na".parse::<DeclarationGroup>()?;
+        let counts = group.answer_counts();
+        assert_eq!(group.size(), 3);
+        assert_eq!(counts[0], 3);
+        assert_eq!(counts[1], 1);
+        assert_eq!(counts[2], 1);
+        assert_eq!(counts[3], 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_any_and_all_answers() -> Result<(), String> {
+        let group = "ab\nac\na".parse::<DeclarationGroup>()?;
+        assert_eq!(group.any_answers().iter().collect::<Vec<u8>>(), b"abc".to_vec());
+        assert_eq!(group.all_answers().iter().collect::<Vec<u8>>(), b"a".to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_group() -> Result<(), String> {
+        let group = "".parse::<DeclarationGroup>()?;
+        assert_eq!(group.size(), 0);
+        assert!(group.any_answers().is_empty());
+        assert!(group.all_answers().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blank_line_in_group() {
+        assert_eq!("ab\n\nac".parse::<DeclarationGroup>().err().unwrap(), "line 2: blank line inside a group");
+        assert_eq!("ab\r\n\r\n".parse::<DeclarationGroup>().err().unwrap(), "line 2: blank line inside a group");
+    }
+
+    #[test]
+    fn test_person_answering_nothing() -> Result<(), String> {
+        let group = DeclarationGroup { data: vec!["ab".parse()?, "".parse()?] };
+        assert_eq!(group.size(), 2);
+        assert_eq!(group.any_answers().len(), 2);
+        assert!(group.all_answers().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_answer() {
+        assert!("ab c".parse::<DeclarationForm>().is_err());
+        assert!("abC".parse::<DeclarationForm>().is_err());
+        assert!("ab\r".parse::<DeclarationForm>().is_err());
+    }
+
+    #[test]
+    fn test_invalid_answer_line_number() {
+        assert_eq!(
+            parse_groups("abc\n\nab\na c\n").err(),
+            Some("line 4: invalid answer ' ' at column 2, expected a-z".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_groups() -> Result<(), String> {
+        let groups = parse_groups("abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n")?;
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[1].size(), 3);
+        assert_eq!(groups[4].size(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_groups_extra_blank_lines() -> Result<(), String> {
+        let groups = parse_groups("\nabc\n\n\n\na\nb\n\n\n")?;
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].size(), 1);
+        assert_eq!(groups[1].size(), 2);
+        assert!(parse_groups("")?.is_empty());
+        assert!(parse_groups("\n\n\n")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_groups_crlf() -> Result<(), String> {
+        let groups = parse_groups("ab\r\nac\r\n\r\nb\r\n")?;
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].all_answers().iter().collect::<Vec<u8>>(), b"a".to_vec());
+        Ok(())
+    }
+
+    fn arb_groups() -> impl Strategy<Value=Vec<Vec<String>>> {
+        prop::collection::vec(prop::collection::vec("[a-z]{1,26}", 1..6), 0..10)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_round_trip_with_any_separators(
+            groups in arb_groups(),
+            separators in prop::collection::vec(1..4usize, 10),
+            leading in 0..3usize,
+            trailing in 0..3usize,
+        ) {
+            let mut text = "\n".repeat(leading);
+            for (group, separator) in groups.iter().zip(separators.iter()) {
+                text += &group.join("\n");
+                text += &"\n".repeat(1 + separator);
+            }
+            text += &"\n".repeat(trailing);
+            let parsed = parse_groups(&text).unwrap();
+            prop_assert_eq!(parsed.len(), groups.len());
+            for (parsed_group, group) in parsed.iter().zip(groups.iter()) {
+                prop_assert_eq!(parsed_group.size(), group.len());
+            }
+        }
+
+        #[test]
+        fn prop_rejects_non_letters_with_line_number(
+            groups in arb_groups().prop_filter("non-empty", |groups| !groups.is_empty()),
+            bad in "[^a-z\n\r]",
+            position in any::<prop::sample::Index>(),
+        ) {
+            let mut lines: Vec<String> = groups.iter().map(|group| group.join("\n")).collect::<Vec<_>>().join("\n\n").lines().map(str::to_string).collect();
+            let form_lines: Vec<usize> = (0..lines.len()).filter(|idx| !lines[*idx].is_empty()).collect();
+            let line_idx = form_lines[position.index(form_lines.len())];
+            lines[line_idx].insert_str(0, &bad);
+            let error = parse_groups(&lines.join("\n")).err().unwrap();
+            let expected_prefix = format!("line {}: ", line_idx + 1);
+            prop_assert!(error.starts_with(&expected_prefix));
+        }
+
+        #[test]
+        fn prop_all_is_subset_of_any(forms in prop::collection::vec("[a-z]{0,26}", 0..6)) {
+            let group = DeclarationGroup { data: forms.iter().map(|form| form.parse().unwrap()).collect() };
+            let any_answers = group.any_answers();
+            let all_answers = group.all_answers();
+            prop_assert_eq!(all_answers & any_answers, all_answers);
+            for form in &group.data {
+                prop_assert_eq!(form.data | any_answers, any_answers);
+                prop_assert_eq!(form.data & all_answers, all_answers);
+            }
+        }
+
+        #[test]
+        fn prop_parse_never_panics(text in "(?s).{0,200}") {
+            let _ = parse_groups(&text);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod answers;
+pub mod declaration;
+pub mod query;
+pub mod statistics;
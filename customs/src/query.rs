@@ -0,0 +1,124 @@
+use std::str::FromStr;
+use crate::answers::AnswerSet;
+use crate::declaration::DeclarationGroup;
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum Query {
+    Any,
+    All,
+    None,
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["any"] => Ok(Query::Any),
+            ["all"] => Ok(Query::All),
+            ["none"] => Ok(Query::None),
+            ["exactly", count] => Ok(Query::Exactly(parse_count(count)?)),
+            ["at", "least", count] => Ok(Query::AtLeast(parse_count(count)?)),
+            _ => Err(format!("Unknown query '{}'", s)),
+        }
+    }
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    s.parse().map_err(|_| format!("Invalid count '{}'", s))
+}
+
+impl Query {
+    fn is_satisfied(&self, yes_count: usize, group_size: usize) -> bool {
+        match self {
+            Query::Any => yes_count > 0,
+            Query::All => yes_count == group_size,
+            Query::None => yes_count == 0,
+            Query::Exactly(count) => yes_count == *count,
+            Query::AtLeast(count) => yes_count >= *count,
+        }
+    }
+
+    // The questions in a group which satisfy the query. A group with nobody in it has no
+    // answers to count, so no question matches, not even for `None` or `Exactly(0)`.
+    pub fn matching_items(&self, group: &DeclarationGroup) -> AnswerSet {
+        if group.size() == 0 {
+            return AnswerSet::empty();
+        }
+        match self {
+            Query::Any => group.any_answers(),
+            Query::All => group.all_answers(),
+            _ => {
+                let counts = group.answer_counts();
+                (b'a'..=b'z')
+                    .zip(counts.iter())
+                    .filter(|(_, yes_count)| self.is_satisfied(**yes_count, group.size()))
+                    .map(|(question, _)| question)
+                    .collect()
+            }
+        }
+    }
+
+    pub fn count(&self, group: &DeclarationGroup) -> usize {
+        self.matching_items(group).len()
+    }
+
+    pub fn sum(&self, groups: &[DeclarationGroup]) -> usize {
+        groups.iter().map(|group| self.count(group)).sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::declaration::parse_groups;
+
+    const EXAMPLE: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+
+    #[test]
+    fn test_parse() -> Result<(), String> {
+        assert_eq!("any".parse::<Query>()?, Query::Any);
+        assert_eq!("all".parse::<Query>()?, Query::All);
+        assert_eq!("none".parse::<Query>()?, Query::None);
+        assert_eq!("exactly 2".parse::<Query>()?, Query::Exactly(2));
+        assert_eq!("at least 3".parse::<Query>()?, Query::AtLeast(3));
+        assert!("some".parse::<Query>().is_err());
+        assert!("exactly two".parse::<Query>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_any() -> Result<(), String> {
+        assert_eq!(Query::Any.sum(&parse_groups(EXAMPLE)?), 11);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_all() -> Result<(), String> {
+        assert_eq!(Query::All.sum(&parse_groups(EXAMPLE)?), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_counts() -> Result<(), String> {
+        let groups = parse_groups(EXAMPLE)?;
+        assert_eq!(Query::None.sum(&groups), 5 * 26 - 11);
+        assert_eq!(Query::Exactly(1).sum(&groups), 3 + 3 + 2 + 1);
+        assert_eq!(Query::AtLeast(2).sum(&groups), 1 + 1);
+        assert_eq!(Query::AtLeast(1).sum(&groups), Query::Any.sum(&groups));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_group() {
+        let empty = DeclarationGroup { data: vec![] };
+        for query in [Query::Any, Query::All, Query::None, Query::Exactly(0), Query::AtLeast(0)] {
+            assert_eq!(query.count(&empty), 0);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::declaration::DeclarationGroup;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct QuestionStatistics {
+    pub question: char,
+    // Number of groups where at least one person answered yes
+    pub any_yes: usize,
+    // Number of groups where every person answered yes
+    pub all_yes: usize,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct Statistics {
+    pub group_count: usize,
+    pub questions: Vec<QuestionStatistics>,
+    // Group size -> number of groups of that size
+    pub group_sizes: BTreeMap<usize, usize>,
+}
+
+impl Statistics {
+    pub fn from_groups(groups: &[DeclarationGroup]) -> Statistics {
+        let mut questions: Vec<QuestionStatistics> = (b'a'..=b'z')
+            .map(|question| QuestionStatistics { question: question as char, any_yes: 0, all_yes: 0 })
+            .collect();
+        let mut group_sizes = BTreeMap::new();
+        for group in groups {
+            let any_answers = group.any_answers();
+            let all_answers = group.all_answers();
+            for (question, stats) in (b'a'..=b'z').zip(questions.iter_mut()) {
+                stats.any_yes += usize::from(any_answers.contains(&question));
+                stats.all_yes += usize::from(all_answers.contains(&question));
+            }
+            *group_sizes.entry(group.size()).or_insert(0) += 1;
+        }
+        Statistics {
+            group_count: groups.len(),
+            questions,
+            group_sizes,
+        }
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut table = format!("{:<8} {:>8} {:>8}\n", "question", "any", "all");
+        for stats in &self.questions {
+            table += &format!("{:<8} {:>8} {:>8}\n", stats.question, stats.any_yes, stats.all_yes);
+        }
+        table += &format!("\n{:<8} {:>8}\n", "size", "groups");
+        for (size, count) in &self.group_sizes {
+            table += &format!("{:<8} {:>8}\n", size, count);
+        }
+        table
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::declaration::parse_groups;
+
+    const EXAMPLE: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let stats = Statistics::from_groups(&parse_groups(EXAMPLE)?);
+        assert_eq!(stats.group_count, 5);
+        assert_eq!(stats.questions[0], QuestionStatistics { question: 'a', any_yes: 4, all_yes: 3 });
+        assert_eq!(stats.questions[1], QuestionStatistics { question: 'b', any_yes: 4, all_yes: 2 });
+        assert_eq!(stats.questions[2], QuestionStatistics { question: 'c', any_yes: 3, all_yes: 1 });
+        assert_eq!(stats.questions[3], QuestionStatistics { question: 'd', any_yes: 0, all_yes: 0 });
+        assert_eq!(stats.group_sizes.iter().collect::<Vec<_>>(), vec![(&1, &2), (&2, &1), (&3, &1), (&4, &1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() -> Result<(), String> {
+        let stats = Statistics::from_groups(&parse_groups("ab\na\n")?);
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["group_count"], 1);
+        assert_eq!(json["questions"][0]["question"], "a");
+        assert_eq!(json["questions"][0]["all_yes"], 1);
+        assert_eq!(json["questions"][1]["any_yes"], 1);
+        assert_eq!(json["questions"][1]["all_yes"], 0);
+        assert_eq!(json["group_sizes"]["2"], 1);
+        Ok(())
+    }
+}
@@ -1,31 +1,85 @@
 use clap::{Arg, App};
 use std::fs;
-use std::str::FromStr;
 use counter::Counter;
+use joltage::chain::{build_chain, differences, parse_adapters, Hardware};
 
 
+// How many gaps of each size there are once every adapter is used, smallest gap first
+fn gap_histogram(adapters: &[u64], hardware: &Hardware) -> Result<Vec<(u64, usize)>, String> {
+    let counted: Counter<_> = differences(&build_chain(adapters, hardware)?, hardware)?
+        .into_iter()
+        .collect();
+    let mut histogram: Vec<(u64, usize)> = counted.iter().map(|(gap, count)| (*gap, *count)).collect();
+    histogram.sort_unstable();
+    Ok(histogram)
+}
+
+// The number of smallest gaps times the number of largest gaps, which is 1-jolt gaps times
+// 3-jolt gaps for the default hardware. When the smallest and largest gaps are the same,
+// every gap is that size, and the product is just the number of gaps.
+fn gap_product(histogram: &[(u64, usize)], hardware: &Hardware) -> usize {
+    let count = |size: u64| histogram.iter().find(|(gap, _)| *gap == size).map_or(0, |(_, count)| *count);
+    if hardware.min_gap == hardware.max_gap {
+        count(hardware.min_gap)
+    } else {
+        count(hardware.min_gap) * count(hardware.max_gap)
+    }
+}
+
 fn main() {
     let args = App::new("Day ten part one of AOC 2020!!")
         .arg(Arg::with_name("input-file").takes_value(true))
+        .arg(Arg::with_name("min-gap").long("min-gap").takes_value(true).default_value("1")
+            .help("The smallest step up in joltage from one adapter to the next"))
+        .arg(Arg::with_name("max-gap").long("max-gap").takes_value(true).default_value("3")
+            .help("The largest step up in joltage from one adapter to the next"))
+        .arg(Arg::with_name("device-offset").long("device-offset").takes_value(true).default_value("3")
+            .help("How far above the highest adapter the device is rated"))
         .get_matches();
-    let mut input_data: Vec<usize> = fs::read_to_string(
+    let hardware = Hardware::new(
+        args.value_of("min-gap").unwrap().parse().unwrap(),
+        args.value_of("max-gap").unwrap().parse().unwrap(),
+        args.value_of("device-offset").unwrap().parse().unwrap(),
+    )
+        .unwrap();
+    let adapters = parse_adapters(&fs::read_to_string(
         args.value_of("input-file").unwrap()
     )
         .unwrap()
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .collect();
-    input_data.sort();
-    input_data.insert(0, 0);
-    input_data.push(input_data.last().unwrap() + 3);
-    let counted: Counter<_> = input_data[0..input_data.len() - 1]
-        .iter()
-        .zip(input_data[1..input_data.len()].iter())
-        .map(|(lower_index, higher_index)| higher_index - lower_index)
-        .collect();
+    )
+        .unwrap();
+    let histogram = gap_histogram(&adapters, &hardware).unwrap();
+    for (gap, count) in &histogram {
+        println!("{} jolts: {}", gap, count);
+    }
+    println!("Product: {}", gap_product(&histogram, &hardware));
+}
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let adapters = parse_adapters("16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4")?;
+        let histogram = gap_histogram(&adapters, &Hardware::default())?;
+        assert_eq!(histogram, vec![(1, 7), (3, 5)]);
+        assert_eq!(gap_product(&histogram, &Hardware::default()), 7 * 5);
+        // Gaps of 1 to 4, where only the device is 4 above the adapter before it
+        let hardware = Hardware::new(1, 4, 4)?;
+        let histogram = gap_histogram(&adapters, &hardware)?;
+        assert_eq!(histogram, vec![(1, 7), (3, 4), (4, 1)]);
+        assert_eq!(gap_product(&histogram, &hardware), 7);
+        Ok(())
+    }
 
-    println!("{}", counted[&1] * counted[&3]);
+    #[test]
+    fn test_single_gap_size() -> Result<(), String> {
+        // Every gap is 2, so there is one bucket, counted once rather than squared
+        let hardware = Hardware::new(2, 2, 2)?;
+        let histogram = gap_histogram(&[2, 4, 6], &hardware)?;
+        assert_eq!(histogram, vec![(2, 4)]);
+        assert_eq!(gap_product(&histogram, &hardware), 4);
+        Ok(())
+    }
 }
-
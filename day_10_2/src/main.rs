@@ -1,68 +1,51 @@
 use clap::{Arg, App};
 use std::fs;
-use std::cmp::min;
-use std::collections::HashMap;
+use joltage::chain::{build_chain, count_arrangements, count_arrangements_naive, parse_adapters, Hardware};
 
 
-fn count_charger_chains_cached(data: &[usize], cache_table: &mut HashMap<usize, usize>) -> usize {
-    let last_placed_charger = &data[0];
-
-    if cache_table.contains_key(last_placed_charger) {
-        cache_table[last_placed_charger]
-    } else {
-        let val =
-            if data.len() == 1 {
-                1
-            } else {
-                (1..min(4, data.len()))
-                    .map(
-                        |idx| if data[idx] <= last_placed_charger + 3 { count_charger_chains_cached(&data[idx..data.len()], cache_table) } else { 0 }
-                    ).sum()
-            };
-        cache_table.insert(*last_placed_charger, val);
-        val
-    }
-}
-
-fn count_charger_chains(data: &[usize], cache: bool) -> usize {
-    if cache {
-        let mut cache_map: HashMap<usize, usize> = HashMap::new();
-        count_charger_chains_cached(&data, &mut cache_map)
-    } else {
-        count_charger_chains_impl(&data)
-    }
-}
-
-
-fn count_charger_chains_impl(data: &[usize]) -> usize {
-    let last_placed_charger = &data[0];
-
-    if data.len() == 1 {
-        1
-    } else {
-        (1..min(4, data.len()))
-            .map(
-                |idx| if data[idx] <= last_placed_charger + 3 { count_charger_chains_impl(&data[idx..data.len()]) } else { 0 }
-            ).sum()
-    }
-}
-
 fn main() {
     let args = App::new("Day ten part two of AOC 2020!!")
         .arg(Arg::with_name("input-file").takes_value(true))
+        .arg(Arg::with_name("naive").long("naive")
+            .help("Try every arrangement one at a time, which takes exponential time on all but small inputs"))
+        .arg(Arg::with_name("min-gap").long("min-gap").takes_value(true).default_value("1")
+            .help("The smallest step up in joltage from one adapter to the next"))
+        .arg(Arg::with_name("max-gap").long("max-gap").takes_value(true).default_value("3")
+            .help("The largest step up in joltage from one adapter to the next"))
+        .arg(Arg::with_name("device-offset").long("device-offset").takes_value(true).default_value("3")
+            .help("How far above the highest adapter the device is rated"))
         .get_matches();
-    let mut input_data: Vec<usize> = fs::read_to_string(
+    let hardware = Hardware::new(
+        args.value_of("min-gap").unwrap().parse().unwrap(),
+        args.value_of("max-gap").unwrap().parse().unwrap(),
+        args.value_of("device-offset").unwrap().parse().unwrap(),
+    )
+        .unwrap();
+    let chain = build_chain(&parse_adapters(&fs::read_to_string(
         args.value_of("input-file").unwrap()
     )
         .unwrap()
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .collect();
-    input_data.sort();
-    input_data.insert(0, 0);
-    input_data.push(input_data.last().unwrap() + 3);
+    )
+        .unwrap(), &hardware)
+        .unwrap();
 
-    println!("{}", count_charger_chains(&input_data, true));
-    println!("{}", count_charger_chains(&input_data, false));
+    let arrangements = if args.is_present("naive") {
+        count_arrangements_naive(&chain, &hardware)
+    } else {
+        count_arrangements(&chain, &hardware)
+    };
+    println!("Arrangements: {}", arrangements);
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let hardware = Hardware::default();
+        let chain = build_chain(&parse_adapters("16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4")?, &hardware)?;
+        assert_eq!(count_arrangements(&chain, &hardware).to_string(), "8");
+        Ok(())
+    }
+}
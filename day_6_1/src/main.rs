@@ -1,86 +1,74 @@
 use clap::{Arg, App};
-use std::str::FromStr;
 use std::fs;
-use std::collections::{HashSet};
+use customs::declaration::parse_groups;
+use customs::query::Query;
 
-struct DeclarationGroup {
-    data: HashSet<u8>,
-}
-
-impl FromStr for DeclarationGroup {
-    type Err = String;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(DeclarationGroup {
-            data: s
-                .bytes()
-                .filter(|b| *b != b'\n')
-                .collect()
-        })
-    }
+fn main() {
+    let args = App::new("Day six part one of AOC 2020!!")
+        .arg(Arg::with_name("input-file").takes_value(true))
+        .arg(Arg::with_name("query").long("query").takes_value(true).default_value("any"))
+        .get_matches();
+    let query: Query = args.value_of("query").unwrap().parse().unwrap();
+    let groups = parse_groups(&fs::read_to_string(
+        args.value_of("input-file").unwrap()
+    )
+        .unwrap()
+    )
+        .unwrap();
+    println!("{}", query.sum(&groups));
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use customs::declaration::DeclarationGroup;
     #[test]
     fn test_declaration_group_fromstr_1() -> Result<(), String> {
-        let passport = "abc".parse::<DeclarationGroup>().unwrap();
-        assert_eq!(passport.data.len(), 3);
-        assert!(passport.data.contains(&b'a'));
-        assert!(passport.data.contains(&b'b'));
-        assert!(passport.data.contains(&b'c'));
+        let group = "abc".parse::<DeclarationGroup>()?;
+        let any_items = Query::Any.matching_items(&group);
+        assert_eq!(any_items.len(), 3);
+        assert!(any_items.contains(&b'a'));
+        assert!(any_items.contains(&b'b'));
+        assert!(any_items.contains(&b'c'));
         Ok(())
     }
     #[test]
     fn test_declaration_group_fromstr_2() -> Result<(), String> {
-        let passport = "a\nb\nc".parse::<DeclarationGroup>().unwrap();
-        assert_eq!(passport.data.len(), 3);
-        assert!(passport.data.contains(&b'a'));
-        assert!(passport.data.contains(&b'b'));
-        assert!(passport.data.contains(&b'c'));
+        let group = "a\nb\nc".parse::<DeclarationGroup>()?;
+        let any_items = Query::Any.matching_items(&group);
+        assert_eq!(any_items.len(), 3);
+        assert!(any_items.contains(&b'a'));
+        assert!(any_items.contains(&b'b'));
+        assert!(any_items.contains(&b'c'));
         Ok(())
     }
     #[test]
     fn test_declaration_group_fromstr_3() -> Result<(), String> {
-        let passport = "ab\nac".parse::<DeclarationGroup>().unwrap();
-        assert_eq!(passport.data.len(), 3);
-        assert!(passport.data.contains(&b'a'));
-        assert!(passport.data.contains(&b'b'));
-        assert!(passport.data.contains(&b'c'));
+        let group = "ab\nac".parse::<DeclarationGroup>()?;
+        let any_items = Query::Any.matching_items(&group);
+        assert_eq!(any_items.len(), 3);
+        assert!(any_items.contains(&b'a'));
+        assert!(any_items.contains(&b'b'));
+        assert!(any_items.contains(&b'c'));
         Ok(())
     }
     #[test]
     fn test_declaration_group_fromstr_4() -> Result<(), String> {
-        let passport = "a\na\na\na".parse::<DeclarationGroup>().unwrap();
-        assert_eq!(passport.data.len(), 1);
-        assert!(passport.data.contains(&b'a'));
+        let group = "a\na\na\na".parse::<DeclarationGroup>()?;
+        let any_items = Query::Any.matching_items(&group);
+        assert_eq!(any_items.len(), 1);
+        assert!(any_items.contains(&b'a'));
         Ok(())
     }
     #[test]
     fn test_declaration_group_fromstr_5() -> Result<(), String> {
-        let passport = "b".parse::<DeclarationGroup>().unwrap();
-        assert_eq!(passport.data.len(), 1);
-        assert!(passport.data.contains(&b'b'));
+        let group = "b".parse::<DeclarationGroup>()?;
+        let any_items = Query::Any.matching_items(&group);
+        assert_eq!(any_items.len(), 1);
+        assert!(any_items.contains(&b'b'));
         Ok(())
     }
 
 }
-
-
-fn main() {
-    let args = App::new("Day six part one of AOC 2020!!")
-        .arg(Arg::with_name("input-file").takes_value(true))
-        .get_matches();
-    let total_count: usize = fs::read_to_string(
-        args.value_of("input-file").unwrap()
-    )
-        .unwrap()
-        .split("\n\n")
-        .map(|group| group.parse::<DeclarationGroup>().unwrap())
-        .map(|group| group.data.len())
-        .sum();
-    println!("{}", total_count);
-}
-
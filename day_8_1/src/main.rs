@@ -1,9 +1,7 @@
-mod emulator;
-
 use clap::{Arg, App};
 use std::fs;
-use emulator::instruction::Instruction;
-use crate::emulator::run_until_repeat;
+use handheld::Instruction;
+use handheld::emulator::run_until_repeat;
 
 
 fn main() {
@@ -19,4 +17,3 @@ fn main() {
         .collect();
     println!("{}", run_until_repeat(&all_instructions));
 }
-
@@ -1,40 +1,16 @@
-mod emulator;
-
 use clap::{Arg, App};
 use std::fs;
-use emulator::instruction::Instruction;
-use crate::emulator::{run_until_repeat_or_halt};
-use crate::emulator::instruction::operation::Operation;
-
-fn swap_op(s: &Instruction) -> Instruction {
-    Instruction {
-        op: match s.op {
-            Operation::Accumulate => Operation::Accumulate,
-            Operation::Jump => Operation::NoOperation,
-            Operation::NoOperation => Operation::Jump,
-        },
-        arg: s.arg
-    }
-}
+use std::process;
+use handheld::Instruction;
+use handheld::repair::{brute_force_repair, find_repair};
 
-fn find_correct_swap(mut all_instructions: Vec<Instruction>) -> i32 {
-    for index in 0..all_instructions.len() {
-        if all_instructions[index].op == Operation::Accumulate {
-            continue;
-        }
-        all_instructions[index] = swap_op(&all_instructions[index]);
-        let state = run_until_repeat_or_halt(&all_instructions);
-        if state.halted {
-            return state.value;
-        }
-        all_instructions[index] = swap_op(&all_instructions[index]);
-    }
-    return 0;
-}
 
 fn main() {
     let args = App::new("Day eight part two of AOC 2020!!")
         .arg(Arg::with_name("input-file").takes_value(true))
+        .arg(Arg::with_name("brute-force").long("brute-force")
+            .help("Re-run the program with every jmp/nop flipped in turn. Programs using jz or jnz \
+                   always take this O(n^2) search, as the linear one only follows unconditional jumps"))
         .get_matches();
     let all_instructions: Vec<Instruction> = fs::read_to_string(
         args.value_of("input-file").unwrap()
@@ -43,6 +19,38 @@ fn main() {
         .lines()
         .map(|line| line.parse::<Instruction>().unwrap())
         .collect();
-    println!("{}", find_correct_swap(all_instructions));
+    let repair = if args.is_present("brute-force") {
+        brute_force_repair(&all_instructions)
+    } else {
+        find_repair(&all_instructions)
+    };
+    match repair {
+        Some(repair) => println!("{}", repair.value),
+        None => {
+            eprintln!("No single jmp/nop flip makes the program halt, or it halts already");
+            process::exit(1);
+        }
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let instructions: Vec<Instruction> = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6".lines().map(|line| line.parse().unwrap()).collect();
+        assert_eq!(find_repair(&instructions).unwrap().value, 8);
+        assert_eq!(brute_force_repair(&instructions).unwrap().value, 8);
+        Ok(())
+    }
+}
@@ -1,39 +1,60 @@
 use clap::{Arg, App};
-use std::fs;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+use xmas::parse_preamble_size;
+use xmas::stream::InvalidNumbers;
 
-fn chunk_is_satisfied(chunk: &[usize], target: usize) -> bool {
-    for idx_a in 0..chunk.len() {
-        for idx_b in 0..chunk.len() {
-            if chunk[idx_a] + chunk[idx_b] == target {
-                return true;
+
+fn main() {
+    let args = App::new("Day nine part one of AOC 2020!!")
+        .arg(Arg::with_name("input-file").takes_value(true).default_value("-")
+            .help("File of numbers, one per line, or - to read them from stdin as they arrive"))
+        .arg(Arg::with_name("preamble-size").takes_value(true).default_value("25")
+            .validator(|s| parse_preamble_size(&s).map(|_| ())))
+        .arg(Arg::with_name("all").long("all")
+            .help("Print every invalid number with its line number as soon as it is read, not just the first"))
+        .get_matches();
+    let reader: Box<dyn BufRead> = match args.value_of("input-file").unwrap() {
+        "-" => Box::new(io::stdin().lock()),
+        path => Box::new(BufReader::new(File::open(path).unwrap())),
+    };
+    let preamble_size = parse_preamble_size(args.value_of("preamble-size").unwrap()).unwrap();
+    let mut invalid_numbers = InvalidNumbers::new(reader, preamble_size);
+    if args.is_present("all") {
+        for invalid in invalid_numbers {
+            match invalid {
+                Ok((line_number, number)) => println!("{}: {}", line_number, number),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    } else {
+        let first = invalid_numbers.find_map(|invalid| match invalid {
+            Ok((_, number)) => Some(number),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        });
+        match first {
+            Some(number) => println!("{}", number),
+            None => {
+                eprintln!("No invalid number found");
+                process::exit(1);
             }
         }
     }
-    false
 }
 
 
-fn find_first_broken_idx(data: &Vec<usize>, preamble_size: usize) -> usize {
-    (preamble_size..data.len())
-        .map(|idx_in_data| (&data[idx_in_data - preamble_size..idx_in_data], idx_in_data))
-        .find(|(chunk, idx_in_data)| !chunk_is_satisfied(chunk, data[*idx_in_data]))
-        .unwrap()
-        .1
-}
-
-fn main() {
-    let args = App::new("Day nine part one of AOC 2020!!")
-        .arg(Arg::with_name("input-file").takes_value(true))
-        .arg(Arg::with_name("preamble-size").takes_value(true))
-        .get_matches();
-    let input_data: Vec<usize> = fs::read_to_string(
-        args.value_of("input-file").unwrap()
-    )
-        .unwrap()
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .collect();
-    let preamble_size: usize = args.value_of("preamble-size").unwrap().parse().unwrap();
-    println!("{}", input_data[find_first_broken_idx(&input_data, preamble_size)]);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_example() {
+        let input = "35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n182\n127\n219\n299\n277\n309\n576";
+        assert_eq!(InvalidNumbers::new(input.as_bytes(), 5).next(), Some(Ok((15, 127))));
+        assert_eq!(parse_preamble_size("25"), Ok(25));
+        assert_eq!(parse_preamble_size("0").err().unwrap(), "The preamble size must be at least 1");
+    }
 }
-
@@ -1,59 +1,59 @@
 use clap::{Arg, App};
 use std::fs;
+use std::io::{self, Read};
+use std::process;
+use xmas::{parse_numbers, parse_preamble_size};
+use xmas::range::{all_contiguous_sums, encryption_weakness, find_contiguous_sum};
+use xmas::window::first_invalid;
 
-fn chunk_is_satisfied(chunk: &[usize], target: usize) -> bool {
-    for idx_a in 0..chunk.len() {
-        for idx_b in 0..chunk.len() {
-            if chunk[idx_a] + chunk[idx_b] == target {
-                return true;
-            }
-        }
-    }
-    false
-}
-
-
-fn find_first_broken_idx(data: &Vec<usize>, preamble_size: usize) -> usize {
-    (preamble_size..data.len())
-        .map(|idx_in_data| (&data[idx_in_data - preamble_size..idx_in_data], idx_in_data))
-        .find(|(chunk, idx_in_data)| !chunk_is_satisfied(chunk, data[*idx_in_data]))
-        .unwrap()
-        .1
-}
-
-fn find_contiguous_addition(data: &Vec<usize>, target: usize) -> &[usize] {
-    for starting_idx in 0..data.len() {
-        for end_idx in starting_idx..data.len() {
-            let range = &data[starting_idx..=end_idx];
-            let sum: usize = range.iter().sum();
-            if sum > target {
-                break;
-            }
-            if sum == target {
-                return range;
-            }
-        }
-    }
-    unreachable!()
-}
 
 fn main() {
     let args = App::new("Day nine part two of AOC 2020!!")
-        .arg(Arg::with_name("input-file").takes_value(true))
-        .arg(Arg::with_name("preamble-size").takes_value(true))
+        .arg(Arg::with_name("input-file").takes_value(true).default_value("-")
+            .help("File of numbers, one per line, or - to read them from stdin"))
+        .arg(Arg::with_name("preamble-size").takes_value(true).default_value("25")
+            .validator(|s| parse_preamble_size(&s).map(|_| ())))
+        .arg(Arg::with_name("all").long("all")
+            .help("Print the weakness of every range that adds up to the invalid number, with where it starts and ends"))
         .get_matches();
-    let input_data: Vec<usize> = fs::read_to_string(
-        args.value_of("input-file").unwrap()
-    )
-        .unwrap()
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .collect();
-    let preamble_size: usize = args.value_of("preamble-size").unwrap().parse().unwrap();
-    let part_one_answer = input_data[find_first_broken_idx(&input_data, preamble_size)];
-    let part_two_range = find_contiguous_addition(&input_data, part_one_answer);
+    let input = match args.value_of("input-file").unwrap() {
+        "-" => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).unwrap();
+            input
+        }
+        path => fs::read_to_string(path).unwrap(),
+    };
+    let input_data = parse_numbers(&input).unwrap();
+    let preamble_size = parse_preamble_size(args.value_of("preamble-size").unwrap()).unwrap();
+    let part_one_answer = match first_invalid(&input_data, preamble_size) {
+        Some((_, number)) => number,
+        None => {
+            eprintln!("No invalid number found");
+            process::exit(1);
+        }
+    };
+    if args.is_present("all") {
+        for range in all_contiguous_sums(&input_data, part_one_answer) {
+            println!("{}..{}: {}", range.start, range.end, encryption_weakness(&input_data, range.clone()));
+        }
+        return;
+    }
+    let part_two_range = find_contiguous_sum(&input_data, part_one_answer)
+        .expect("No range of two or more numbers adds up to the invalid number");
+    println!("{}", encryption_weakness(&input_data, part_two_range));
+}
 
 
-    println!("{}", part_two_range.iter().min().unwrap() + part_two_range.iter().max().unwrap());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let data = parse_numbers("35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n182\n127\n219\n299\n277\n309\n576")?;
+        let range = find_contiguous_sum(&data, first_invalid(&data, 5).unwrap().1).unwrap();
+        assert_eq!(encryption_weakness(&data, range), 62);
+        assert_eq!(parse_numbers("1\r\n\r\n 2 \n\n")?, vec![1, 2]);
+        Ok(())
+    }
 }
-
@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::instruction::Instruction;
+use crate::instruction::operation::Operation;
+use crate::instruction::register::Register;
+
+// Assembly is a superset of the plain program text read by `parse_program`:
+//
+//     # Comments run from '#' to the end of the line
+//     const STEP = 3
+//     loop: acc b +STEP    # labels can sit before an instruction...
+//     jnz b loop
+//     end:                 # ...or on a line of their own
+//
+// Blank lines are ignored. A label names the address of the next instruction, and may be used
+// as the argument of jmp, jz, jnz and nop, where it is replaced by the offset to that address.
+// A constant may be used as the argument of any operation, with an optional sign.
+
+enum Symbol {
+    Constant(i32),
+    Label(usize),
+}
+
+// An instruction line waiting for its labels to be resolved
+struct Line<'a> {
+    line_number: usize,
+    address: usize,
+    text: &'a str,
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn takes_label(op: Operation) -> bool {
+    matches!(op, Operation::Jump | Operation::JumpIfZero | Operation::JumpIfNotZero | Operation::NoOperation)
+}
+
+fn define<'a>(symbols: &mut HashMap<&'a str, Symbol>, name: &'a str, symbol: Symbol) -> Result<(), String> {
+    if !is_identifier(name) {
+        return Err(format!("Invalid name '{}'", name));
+    }
+    if name.parse::<Register>().is_ok() {
+        return Err(format!("'{}' is a register and cannot be redefined", name));
+    }
+    if symbols.insert(name, symbol).is_some() {
+        return Err(format!("'{}' is already defined", name));
+    }
+    Ok(())
+}
+
+fn parse_constant(definition: &str, symbols: &HashMap<&str, Symbol>) -> Result<i32, String> {
+    match definition.trim().parse() {
+        Ok(value) => Ok(value),
+        Err(_) => match symbols.get(definition.trim()) {
+            Some(Symbol::Constant(value)) => Ok(*value),
+            _ => Err(format!("Invalid constant value '{}'", definition.trim())),
+        },
+    }
+}
+
+// Replaces a named argument with its value, leaving everything else for `Instruction` to parse
+fn resolve(line: &Line<'_>, symbols: &HashMap<&str, Symbol>) -> Result<Instruction, String> {
+    let mut operands: Vec<&str> = line.text.split_whitespace().collect();
+    let op = operands[0].parse::<Operation>()?;
+    let last = operands.len() - 1;
+    if last == 0 {
+        return line.text.parse();
+    }
+    let (sign, name) = match operands[last].strip_prefix('-') {
+        Some(name) => (-1, name),
+        None => (1, operands[last].strip_prefix('+').unwrap_or(operands[last])),
+    };
+    let value = match symbols.get(name) {
+        Some(Symbol::Constant(value)) => {
+            Some(value.checked_mul(sign).ok_or_else(|| format!("'{}' is out of range", operands[last]))?)
+        }
+        Some(Symbol::Label(address)) if takes_label(op) && name == operands[last] => {
+            Some(*address as i32 - line.address as i32)
+        }
+        Some(Symbol::Label(_)) => return Err(format!("Label '{}' cannot be used as the argument of {}", name, op)),
+        None if is_identifier(name) && name.parse::<Register>().is_err() => {
+            return Err(format!("Undefined name '{}'", name));
+        }
+        None => None,
+    };
+    let resolved;
+    if let Some(value) = value {
+        resolved = format!("{:+}", value);
+        operands[last] = &resolved;
+    }
+    operands.join(" ").parse()
+}
+
+pub fn assemble(s: &str) -> Result<Vec<Instruction>, String> {
+    let mut symbols = HashMap::new();
+    let mut lines = Vec::new();
+    for (line, line_number) in s.lines().zip(1..) {
+        let in_line = |e: String| format!("line {}: {}", line_number, e);
+        let mut text = line.split('#').next().unwrap().trim();
+        if let Some(definition) = text.strip_prefix("const ") {
+            let (name, value) = definition
+                .split_once('=')
+                .ok_or_else(|| in_line(format!("Expected 'const NAME = value', found '{}'", text)))?;
+            let value = parse_constant(value, &symbols).map_err(in_line)?;
+            define(&mut symbols, name.trim(), Symbol::Constant(value)).map_err(in_line)?;
+            continue;
+        }
+        while let Some((label, rest)) = text.split_once(':') {
+            define(&mut symbols, label.trim(), Symbol::Label(lines.len())).map_err(in_line)?;
+            text = rest.trim();
+        }
+        if !text.is_empty() {
+            lines.push(Line { line_number, address: lines.len(), text });
+        }
+    }
+    lines
+        .iter()
+        .map(|line| resolve(line, &symbols).map_err(|e| format!("line {}: {}", line.line_number, e)))
+        .collect()
+}
+
+// The canonical text of a program, one instruction per line, as read by `parse_program`
+pub fn disassemble(program: &[Instruction]) -> String {
+    program.iter().map(|instruction| format!("{}\n", instruction)).collect()
+}
+
+// Like `disassemble`, but with a label on every address a jump lands on, and jumps written
+// in terms of those labels. Jumps out of the program keep their numeric offset.
+pub fn disassemble_with_labels(program: &[Instruction]) -> String {
+    let target = |address: usize, instruction: &Instruction| {
+        let target = address as i64 + instruction.arg as i64;
+        match instruction.op {
+            Operation::Jump | Operation::JumpIfZero | Operation::JumpIfNotZero
+                if (0..=program.len() as i64).contains(&target) => Some(target as usize),
+            _ => None,
+        }
+    };
+    let labels: BTreeMap<usize, String> = program
+        .iter()
+        .enumerate()
+        .filter_map(|(address, instruction)| target(address, instruction))
+        .map(|address| (address, format!("L{}", address)))
+        .collect();
+
+    let mut text = String::new();
+    for (address, instruction) in program.iter().enumerate() {
+        if let Some(label) = labels.get(&address) {
+            text += &format!("{}:\n", label);
+        }
+        match target(address, instruction) {
+            Some(target) => {
+                let canonical = instruction.to_string();
+                let (operation, _) = canonical.rsplit_once(' ').unwrap();
+                text += &format!("    {} {}\n", operation, labels[&target]);
+            }
+            None => text += &format!("    {}\n", instruction),
+        }
+    }
+    if let Some(label) = labels.get(&program.len()) {
+        text += &format!("{}:\n", label);
+    }
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::{Machine, RunOutcome};
+    use crate::instruction::parse_program;
+    use crate::test_support::{instruction, EXAMPLE};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_labels_and_comments() -> Result<(), String> {
+        let source = "\
+# Counts b down from COUNT, doubling a each time
+const COUNT = 3
+
+    acc +1
+    acc b +COUNT
+loop: mul +2        # a *= 2
+    acc b -1
+    jnz b loop
+    jmp end
+    acc +100
+end:
+";
+        let program = assemble(source)?;
+        assert_eq!(program, parse_program("acc +1\nacc b +3\nmul +2\nacc b -1\njnz b -2\njmp +2\nacc +100")?);
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(None), RunOutcome::Halted);
+        assert_eq!(machine.state.value, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_constants() -> Result<(), String> {
+        let program = assemble("const A1 = -4\nconst A2 = A1\nacc -A2\nmul A1\nout b")?;
+        assert_eq!(program, parse_program("acc +4\nmul -4\nout b")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_programs_assemble() -> Result<(), String> {
+        assert_eq!(assemble(EXAMPLE)?, parse_program(EXAMPLE)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(assemble("jmp nowhere").err().unwrap(), "line 1: Undefined name 'nowhere'");
+        assert_eq!(assemble("x:\nnop +0\nx: hlt").err().unwrap(), "line 3: 'x' is already defined");
+        assert_eq!(assemble("b: hlt").err().unwrap(), "line 1: 'b' is a register and cannot be redefined");
+        assert_eq!(assemble("start: acc start").err().unwrap(), "line 1: Label 'start' cannot be used as the argument of acc");
+        assert_eq!(assemble("const N 3").err().unwrap(), "line 1: Expected 'const NAME = value', found 'const N 3'");
+        assert_eq!(assemble("const N = M").err().unwrap(), "line 1: Invalid constant value 'M'");
+        assert_eq!(assemble("const MIN = -2147483648\nacc -MIN").err().unwrap(), "line 2: '-MIN' is out of range");
+        assert_eq!(assemble("\n\nacc").err().unwrap(), "line 3: acc expects an optional register and an argument, found 'acc'");
+    }
+
+    #[test]
+    fn test_disassemble() -> Result<(), String> {
+        let program = parse_program("nop +0\nacc +1\njmp +4\nacc b +3\njz b -3\nacc -99\njmp -6\nhlt")?;
+        assert_eq!(disassemble(&program), "nop +0\nacc +1\njmp +4\nacc b +3\njz b -3\nacc -99\njmp -6\nhlt\n");
+        assert_eq!(
+            disassemble_with_labels(&program),
+            "L0:\n    nop +0\nL1:\n    acc +1\n    jmp L6\n    acc b +3\n    jz b L1\n    acc -99\nL6:\n    jmp L0\n    hlt\n",
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_disassembly_round_trips(program in prop::collection::vec(instruction(-10..10i32), 0..20)) {
+            prop_assert_eq!(&parse_program(&disassemble(&program)).unwrap(), &program);
+            prop_assert_eq!(&assemble(&disassemble_with_labels(&program)).unwrap(), &program);
+        }
+    }
+}
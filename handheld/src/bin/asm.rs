@@ -0,0 +1,26 @@
+use clap::{Arg, App};
+use std::fs;
+use handheld::assembler::{assemble, disassemble, disassemble_with_labels};
+use handheld::bytecode::{encode, load_program};
+
+
+fn main() {
+    let args = App::new("Assemble a handheld program with labels and constants into plain instructions")
+        .arg(Arg::with_name("input-file").takes_value(true).required(true))
+        .arg(Arg::with_name("disassemble").long("disassemble")
+            .help("Read plain instructions or bytecode and write them back out with labels for every jump target"))
+        .arg(Arg::with_name("bytecode").long("bytecode").takes_value(true).value_name("output-file")
+            .conflicts_with("disassemble")
+            .help("Write the assembled program to this file as bytecode instead of printing it"))
+        .get_matches();
+    let input = args.value_of("input-file").unwrap();
+    if args.is_present("disassemble") {
+        print!("{}", disassemble_with_labels(&load_program(&fs::read(input).unwrap()).unwrap()));
+        return;
+    }
+    let program = assemble(&fs::read_to_string(input).unwrap()).unwrap();
+    match args.value_of("bytecode") {
+        Some(output) => fs::write(output, encode(&program)).unwrap(),
+        None => print!("{}", disassemble(&program)),
+    }
+}
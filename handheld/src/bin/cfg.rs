@@ -0,0 +1,25 @@
+use clap::{Arg, App};
+use std::fs;
+use handheld::cfg::ControlFlowGraph;
+use handheld::bytecode::load_program;
+
+
+fn main() {
+    let args = App::new("Control flow analysis of a handheld program")
+        .arg(Arg::with_name("input-file").takes_value(true).required(true))
+        .arg(Arg::with_name("dot").long("dot")
+            .help("Export the control flow graph as Graphviz DOT instead of a report"))
+        .get_matches();
+    let program = load_program(&fs::read(
+        args.value_of("input-file").unwrap()
+    )
+        .unwrap()
+    )
+        .unwrap();
+    let cfg = ControlFlowGraph::new(&program);
+    if args.is_present("dot") {
+        print!("{}", cfg.to_dot());
+    } else {
+        print!("{}", cfg.report());
+    }
+}
@@ -0,0 +1,37 @@
+use clap::{Arg, App};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use handheld::debugger::{Command, Debugger};
+use handheld::bytecode::load_program;
+
+
+fn main() {
+    let args = App::new("Interactive debugger for the handheld game console")
+        .arg(Arg::with_name("input-file").takes_value(true).required(true))
+        .get_matches();
+    let program = load_program(&fs::read(
+        args.value_of("input-file").unwrap()
+    )
+        .unwrap()
+    )
+        .unwrap();
+    let mut debugger = Debugger::new(&program);
+    println!("{}", debugger.execute(Command::Print));
+    let stdin = io::stdin();
+    loop {
+        print!("(handheld) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<Command>() {
+            Ok(Command::Quit) => break,
+            Ok(command) => println!("{}", debugger.execute(command)),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use clap::{Arg, App};
+use std::process;
+use handheld::assembler::disassemble;
+use handheld::fuzz::check_invariants;
+use handheld::generator::{Generator, Rng, Termination};
+
+
+fn main() {
+    let args = App::new("Check the emulator and repair search against random programs")
+        .arg(Arg::with_name("iterations").long("iterations").takes_value(true).default_value("10000"))
+        .arg(Arg::with_name("seed").long("seed").takes_value(true).default_value("0")
+            .help("Program i is generated from seed + i, so a failure can be reproduced with the generate command"))
+        .arg(Arg::with_name("max-length").long("max-length").takes_value(true).default_value("100"))
+        .arg(Arg::with_name("extended").long("extended")
+            .help("Use registers and every operation, not just acc, jmp and nop"))
+        .get_matches();
+    let iterations: u64 = args.value_of("iterations").unwrap().parse().unwrap();
+    let first_seed: u64 = args.value_of("seed").unwrap().parse().unwrap();
+    let max_length: usize = args.value_of("max-length").unwrap().parse().unwrap();
+    let terminations = [Termination::Halts, Termination::Loops, Termination::Either];
+
+    let mut rng = Rng::new(first_seed);
+    for seed in first_seed..first_seed + iterations {
+        let length = 1 + rng.below(max_length.max(1));
+        let termination = terminations[rng.below(terminations.len())];
+        let mut generator = Generator::new(length).with_termination(termination);
+        if args.is_present("extended") {
+            generator = generator.with_extended_operations();
+        }
+        let program = generator.generate(&mut Rng::new(seed));
+        if let Err(e) = check_invariants(&program, termination) {
+            println!("seed {} ({} instructions, {:?}): {}", seed, length, termination, e);
+            print!("{}", disassemble(&program));
+            process::exit(1);
+        }
+    }
+    println!("{} programs checked", iterations);
+}
@@ -0,0 +1,32 @@
+use clap::{Arg, App};
+use std::fs;
+use handheld::assembler::disassemble;
+use handheld::bytecode::encode;
+use handheld::generator::{Generator, Rng, Termination};
+
+
+fn main() {
+    let args = App::new("Generate a random handheld program")
+        .arg(Arg::with_name("length").long("length").takes_value(true).default_value("100"))
+        .arg(Arg::with_name("termination").long("termination").takes_value(true)
+            .possible_values(&["halts", "loops", "either"]).default_value("either"))
+        .arg(Arg::with_name("seed").long("seed").takes_value(true).default_value("0"))
+        .arg(Arg::with_name("max-arg").long("max-arg").takes_value(true).default_value("100")
+            .help("The largest magnitude of an acc or mul argument"))
+        .arg(Arg::with_name("extended").long("extended")
+            .help("Use registers and every operation, not just acc, jmp and nop"))
+        .arg(Arg::with_name("bytecode").long("bytecode").takes_value(true).value_name("output-file")
+            .help("Write the program to this file as bytecode instead of printing it"))
+        .get_matches();
+    let mut generator = Generator::new(args.value_of("length").unwrap().parse().unwrap())
+        .with_termination(args.value_of("termination").unwrap().parse::<Termination>().unwrap())
+        .with_max_arg(args.value_of("max-arg").unwrap().parse().unwrap());
+    if args.is_present("extended") {
+        generator = generator.with_extended_operations();
+    }
+    let program = generator.generate(&mut Rng::new(args.value_of("seed").unwrap().parse().unwrap()));
+    match args.value_of("bytecode") {
+        Some(output) => fs::write(output, encode(&program)).unwrap(),
+        None => print!("{}", disassemble(&program)),
+    }
+}
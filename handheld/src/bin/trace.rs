@@ -0,0 +1,43 @@
+use clap::{Arg, App};
+use std::fs;
+use handheld::Machine;
+use handheld::bytecode::load_program;
+use handheld::snapshot::MachineSnapshot;
+
+
+fn main() {
+    let args = App::new("Record every instruction a handheld program executes")
+        .arg(Arg::with_name("input-file").takes_value(true).required(true))
+        .arg(Arg::with_name("format").long("format").takes_value(true).possible_values(&["summary", "csv", "json"]).default_value("summary"))
+        .arg(Arg::with_name("step-limit").long("step-limit").takes_value(true)
+            .help("Keep running through loops for up to this many instructions, instead of stopping at the first repeat"))
+        .arg(Arg::with_name("resume").long("resume").takes_value(true).value_name("snapshot-file")
+            .help("Carry on from a snapshot written by --checkpoint"))
+        .arg(Arg::with_name("checkpoint").long("checkpoint").takes_value(true).value_name("snapshot-file")
+            .help("Write a snapshot of the machine to this file when it stops"))
+        .get_matches();
+    let program = load_program(&fs::read(
+        args.value_of("input-file").unwrap()
+    )
+        .unwrap()
+    )
+        .unwrap();
+    let machine = match args.value_of("resume") {
+        Some(file) => Machine::restore(&program, MachineSnapshot::from_json(&fs::read_to_string(file).unwrap()).unwrap()).unwrap(),
+        None => Machine::new(&program),
+    };
+    let mut machine = machine.with_trace();
+    let outcome = match args.value_of("step-limit") {
+        Some(limit) => machine.run_steps(limit.parse().unwrap()),
+        None => machine.run(None),
+    };
+    if let Some(file) = args.value_of("checkpoint") {
+        fs::write(file, machine.snapshot().to_json()).unwrap();
+    }
+    let trace = machine.take_trace().unwrap();
+    match args.value_of("format").unwrap() {
+        "csv" => print!("{}", trace.to_csv()),
+        "json" => println!("{}", trace.to_json()),
+        _ => print!("{}", trace.summary(outcome)),
+    }
+}
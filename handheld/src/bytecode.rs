@@ -0,0 +1,228 @@
+use crate::instruction::{parse_program, Instruction};
+use crate::instruction::operation::Operation;
+use crate::instruction::register::REGISTERS;
+
+// Layout of a version 1 file:
+//
+//     "HHBC"             magic
+//     u8                 version
+//     varint             number of instructions
+//     per instruction:
+//       u8               operation in the low nibble, register index in bits 4-5
+//       varint           zigzag encoded argument, only for operations which take one
+//     u32 little endian  FNV-1a checksum of every byte before it
+//
+// Varints are LEB128: seven bits per byte, least significant first, high bit set on all but
+// the last byte.
+pub const MAGIC: &[u8; 4] = b"HHBC";
+pub const VERSION: u8 = 1;
+
+fn opcode(op: Operation) -> u8 {
+    match op {
+        Operation::Accumulate => 0,
+        Operation::Jump => 1,
+        Operation::NoOperation => 2,
+        Operation::JumpIfZero => 3,
+        Operation::JumpIfNotZero => 4,
+        Operation::Multiply => 5,
+        Operation::Output => 6,
+        Operation::Halt => 7,
+    }
+}
+
+fn operation(opcode: u8) -> Option<Operation> {
+    match opcode {
+        0 => Some(Operation::Accumulate),
+        1 => Some(Operation::Jump),
+        2 => Some(Operation::NoOperation),
+        3 => Some(Operation::JumpIfZero),
+        4 => Some(Operation::JumpIfNotZero),
+        5 => Some(Operation::Multiply),
+        6 => Some(Operation::Output),
+        7 => Some(Operation::Halt),
+        _ => None,
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x01000193))
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn unzigzag(value: u32) -> i32 {
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+pub fn encode(program: &[Instruction]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    write_varint(&mut bytes, program.len() as u32);
+    for instruction in program {
+        bytes.push(opcode(instruction.op) | (instruction.register.index() as u8) << 4);
+        if instruction.op.takes_argument() {
+            write_varint(&mut bytes, zigzag(instruction.arg));
+        }
+    }
+    let checksum = checksum(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self.bytes.get(self.offset).ok_or("Unexpected end of bytecode")?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u32, String> {
+        let start = self.offset;
+        let mut value: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            // The fifth byte only has room for the top four bits of a u32
+            if shift == 28 && byte > 0x0f {
+                break;
+            }
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(format!("byte {}: varint does not fit in 32 bits", start))
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<Instruction>, String> {
+    if !bytes.starts_with(MAGIC) {
+        return Err("Not handheld bytecode".to_string());
+    }
+    if bytes.len() < MAGIC.len() + 1 + 4 {
+        return Err("Unexpected end of bytecode".to_string());
+    }
+    let (body, stored) = bytes.split_at(bytes.len() - 4);
+    let version = body[MAGIC.len()];
+    if version != VERSION {
+        return Err(format!("Unsupported bytecode version {}, expected {}", version, VERSION));
+    }
+    let stored = u32::from_le_bytes([stored[0], stored[1], stored[2], stored[3]]);
+    if checksum(body) != stored {
+        return Err(format!("Checksum mismatch: expected {:08x}, found {:08x}", stored, checksum(body)));
+    }
+
+    let mut reader = Reader { bytes: body, offset: MAGIC.len() + 1 };
+    let count = reader.varint()? as usize;
+    // Every instruction takes at least a byte, which bounds the allocation for a bad count
+    let mut program = Vec::with_capacity(count.min(body.len()));
+    for _ in 0..count {
+        let offset = reader.offset;
+        let byte = reader.byte()?;
+        let op = operation(byte & 0x0f).ok_or_else(|| format!("byte {}: unknown opcode {}", offset, byte & 0x0f))?;
+        let register = *REGISTERS.get((byte >> 4) as usize).ok_or_else(|| format!("byte {}: unknown register {}", offset, byte >> 4))?;
+        let arg = if op.takes_argument() { unzigzag(reader.varint()?) } else { 0 };
+        program.push(Instruction { op, register, arg });
+    }
+    if reader.offset != body.len() {
+        return Err(format!("byte {}: unexpected data after the last instruction", reader.offset));
+    }
+    Ok(program)
+}
+
+// Reads a program from either bytecode or text, telling them apart by the magic number
+pub fn load_program(bytes: &[u8]) -> Result<Vec<Instruction>, String> {
+    if bytes.starts_with(MAGIC) {
+        decode(bytes)
+    } else {
+        parse_program(std::str::from_utf8(bytes).map_err(|e| e.to_string())?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::disassemble;
+    use crate::test_support::{instruction, EXAMPLE};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let text = format!("{}\n", EXAMPLE);
+        let program = parse_program(&text)?;
+        let bytes = encode(&program);
+        // Header, an opcode and argument byte per instruction plus a second byte for -99, checksum
+        assert_eq!(bytes.len(), 6 + 2 * 9 + 1 + 4);
+        assert_eq!(&bytes[..8], &[b'H', b'H', b'B', b'C', 1, 9, 2, 0]);
+        assert_eq!(disassemble(&decode(&bytes)?), text);
+        assert_eq!(load_program(&bytes)?, load_program(text.as_bytes())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_varint() -> Result<(), String> {
+        for value in [0, 1, -1, 63, -64, 64, 300, -300, i32::MAX, i32::MIN] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, zigzag(value));
+            assert_eq!(unzigzag(Reader { bytes: &bytes, offset: 0 }.varint()?), value);
+        }
+        assert_eq!(Reader { bytes: &[0xff, 0xff, 0xff, 0xff, 0x1f], offset: 0 }.varint().err().unwrap(), "byte 0: varint does not fit in 32 bits");
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let bytes = encode(&[Instruction::new(Operation::Jump, 300), Instruction::new(Operation::Halt, 0)]);
+        assert_eq!(decode(b"acc +1").err().unwrap(), "Not handheld bytecode");
+        assert_eq!(decode(&bytes[..6]).err().unwrap(), "Unexpected end of bytecode");
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 2;
+        assert_eq!(decode(&wrong_version).err().unwrap(), "Unsupported bytecode version 2, expected 1");
+
+        let mut corrupted = bytes.clone();
+        corrupted[7] ^= 1;
+        assert!(decode(&corrupted).err().unwrap().starts_with("Checksum mismatch"));
+
+        // Re-sign the corrupted bytes so decoding gets past the checksum
+        let resign = |mut body: Vec<u8>| {
+            let checksum = checksum(&body);
+            body.extend_from_slice(&checksum.to_le_bytes());
+            body
+        };
+        let body = bytes[..bytes.len() - 4].to_vec();
+        let mut bad_opcode = body.clone();
+        bad_opcode[9] = 0x0f;
+        assert_eq!(decode(&resign(bad_opcode)).err().unwrap(), "byte 9: unknown opcode 15");
+        assert_eq!(decode(&resign(body[..9].to_vec())).err().unwrap(), "Unexpected end of bytecode");
+        let mut trailing = body.clone();
+        trailing.push(0);
+        assert_eq!(decode(&resign(trailing)).err().unwrap(), "byte 10: unexpected data after the last instruction");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_encoding_round_trips(program in prop::collection::vec(instruction(any::<i32>()), 0..200)) {
+            let bytes = encode(&program);
+            prop_assert_eq!(&decode(&bytes).unwrap(), &program);
+            let text = disassemble(&program);
+            prop_assert_eq!(&load_program(text.as_bytes()).unwrap(), &program);
+            prop_assert_eq!(&disassemble(&load_program(&bytes).unwrap()), &text);
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::BTreeSet;
+use crate::instruction::Instruction;
+use crate::instruction::operation::Operation;
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub enum Edge {
+    Block(usize),
+    // Just past the last instruction, where the program halts
+    Exit,
+    // Any other address outside of memory
+    OutOfRange(i64),
+}
+
+// A run of instructions with a single entry at `start`, which always execute in order
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct BasicBlock {
+    pub start: usize,
+    // Exclusive
+    pub end: usize,
+    pub successors: Vec<Edge>,
+}
+
+pub struct ControlFlowGraph<'a> {
+    pub memory: &'a [Instruction],
+    pub blocks: Vec<BasicBlock>,
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Analysis {
+    // Blocks which no execution can reach
+    pub unreachable: Vec<usize>,
+    // Reachable groups of blocks which loop forever whatever the accumulator holds
+    pub infinite_loops: Vec<Vec<usize>>,
+    // (address of the jump, where it jumps to)
+    pub out_of_range: Vec<(usize, i64)>,
+}
+
+// Every address the instruction pointer may move to after `instruction` runs at `ip`. A
+// conditional jump may go either way, as the register it tests is not known statically.
+fn targets(ip: usize, instruction: &Instruction, len: usize) -> Vec<i64> {
+    let next = ip as i64 + 1;
+    let jump = ip as i64 + instruction.arg as i64;
+    match instruction.op {
+        Operation::Jump => vec![jump],
+        Operation::JumpIfZero | Operation::JumpIfNotZero if jump == next => vec![next],
+        Operation::JumpIfZero | Operation::JumpIfNotZero => vec![next, jump],
+        Operation::Halt => vec![len as i64],
+        Operation::Accumulate | Operation::NoOperation | Operation::Multiply | Operation::Output => vec![next],
+    }
+}
+
+fn falls_through(ip: usize, instruction: &Instruction, len: usize) -> bool {
+    targets(ip, instruction, len) == vec![ip as i64 + 1]
+}
+
+impl<'a> ControlFlowGraph<'a> {
+    pub fn new(memory: &'a [Instruction]) -> ControlFlowGraph<'a> {
+        let len = memory.len();
+        let mut leaders: BTreeSet<usize> = BTreeSet::new();
+        if len > 0 {
+            leaders.insert(0);
+        }
+        for (ip, instruction) in memory.iter().enumerate() {
+            if !falls_through(ip, instruction, len) {
+                leaders.insert(ip + 1);
+                leaders.extend(targets(ip, instruction, len).into_iter().filter(|target| *target >= 0).map(|target| target as usize));
+            }
+        }
+        let leaders: Vec<usize> = leaders.into_iter().filter(|leader| *leader < len).collect();
+        let block_at = |address: i64| -> Edge {
+            if address == len as i64 {
+                Edge::Exit
+            } else if address < 0 || address > len as i64 {
+                Edge::OutOfRange(address)
+            } else {
+                Edge::Block(leaders.binary_search(&(address as usize)).unwrap())
+            }
+        };
+        let blocks = leaders
+            .iter()
+            .enumerate()
+            .map(|(idx, start)| {
+                let end = leaders.get(idx + 1).copied().unwrap_or(len);
+                BasicBlock {
+                    start: *start,
+                    end,
+                    successors: targets(end - 1, &memory[end - 1], len).into_iter().map(block_at).collect(),
+                }
+            })
+            .collect();
+        ControlFlowGraph { memory, blocks }
+    }
+
+    fn block_successors(&self, block: usize) -> impl Iterator<Item=usize> + '_ {
+        self.blocks[block].successors.iter().filter_map(|edge| match edge {
+            Edge::Block(next) => Some(*next),
+            _ => None,
+        })
+    }
+
+    fn reachable(&self) -> Vec<bool> {
+        let mut reached = vec![false; self.blocks.len()];
+        let mut stack = Vec::new();
+        if !self.blocks.is_empty() {
+            reached[0] = true;
+            stack.push(0);
+        }
+        while let Some(block) = stack.pop() {
+            for next in self.block_successors(block) {
+                if !reached[next] {
+                    reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        reached
+    }
+
+    // Blocks from which the program can stop, either by reaching the end or by jumping out of
+    // memory, which stops it just the same
+    fn can_exit(&self) -> Vec<bool> {
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.blocks.len()];
+        let mut stack = Vec::new();
+        let mut exits = vec![false; self.blocks.len()];
+        for (block, contents) in self.blocks.iter().enumerate() {
+            for next in self.block_successors(block) {
+                predecessors[next].push(block);
+            }
+            if contents.successors.iter().any(|edge| matches!(edge, Edge::Exit | Edge::OutOfRange(_))) {
+                exits[block] = true;
+                stack.push(block);
+            }
+        }
+        while let Some(block) = stack.pop() {
+            for previous in &predecessors[block] {
+                if !exits[*previous] {
+                    exits[*previous] = true;
+                    stack.push(*previous);
+                }
+            }
+        }
+        exits
+    }
+
+    // Strongly connected components which contain a cycle, by Kosaraju's algorithm
+    fn cycles(&self, include: &[bool]) -> Vec<Vec<usize>> {
+        let count = self.blocks.len();
+        let mut order = Vec::new();
+        let mut visited = vec![false; count];
+        for root in (0..count).filter(|block| include[*block]) {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            let mut stack: Vec<(usize, Vec<usize>)> = vec![(root, self.block_successors(root).collect())];
+            while let Some((block, mut pending)) = stack.pop() {
+                match pending.pop() {
+                    Some(next) => {
+                        stack.push((block, pending));
+                        if include[next] && !visited[next] {
+                            visited[next] = true;
+                            stack.push((next, self.block_successors(next).collect()));
+                        }
+                    }
+                    None => order.push(block),
+                }
+            }
+        }
+
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for block in (0..count).filter(|block| include[*block]) {
+            for next in self.block_successors(block).filter(|next| include[*next]) {
+                predecessors[next].push(block);
+            }
+        }
+        let mut component = vec![usize::MAX; count];
+        let mut components = Vec::new();
+        for root in order.into_iter().rev() {
+            if component[root] != usize::MAX {
+                continue;
+            }
+            let mut members = vec![root];
+            component[root] = components.len();
+            let mut stack = vec![root];
+            while let Some(block) = stack.pop() {
+                for previous in &predecessors[block] {
+                    if component[*previous] == usize::MAX {
+                        component[*previous] = components.len();
+                        members.push(*previous);
+                        stack.push(*previous);
+                    }
+                }
+            }
+            members.sort_unstable();
+            components.push(members);
+        }
+        components
+            .into_iter()
+            .filter(|members| members.len() > 1 || self.block_successors(members[0]).any(|next| next == members[0]))
+            .collect()
+    }
+
+    pub fn analyse(&self) -> Analysis {
+        let reachable = self.reachable();
+        let can_exit = self.can_exit();
+        let stuck: Vec<bool> = reachable.iter().zip(can_exit.iter()).map(|(r, e)| *r && !*e).collect();
+        let mut infinite_loops = self.cycles(&stuck);
+        infinite_loops.sort();
+        Analysis {
+            unreachable: (0..self.blocks.len()).filter(|block| !reachable[*block]).collect(),
+            infinite_loops,
+            out_of_range: self.memory
+                .iter()
+                .enumerate()
+                .flat_map(|(ip, instruction)| targets(ip, instruction, self.memory.len())
+                    .into_iter()
+                    .filter(|target| *target < 0 || *target > self.memory.len() as i64)
+                    .map(move |target| (ip, target)))
+                .collect(),
+        }
+    }
+
+    pub fn describe_block(&self, block: usize) -> String {
+        let contents = &self.blocks[block];
+        if contents.end - contents.start == 1 {
+            format!("block {} ({})", block, contents.start)
+        } else {
+            format!("block {} ({}-{})", block, contents.start, contents.end - 1)
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let analysis = self.analyse();
+        let mut report = format!("{} instructions in {} basic blocks\n", self.memory.len(), self.blocks.len());
+        for (block, contents) in self.blocks.iter().enumerate() {
+            let successors: Vec<String> = contents.successors.iter().map(|edge| match edge {
+                Edge::Block(next) => format!("block {}", next),
+                Edge::Exit => "exit".to_string(),
+                Edge::OutOfRange(address) => format!("out of range ({})", address),
+            }).collect();
+            report += &format!("  {} -> {}\n", self.describe_block(block), successors.join(", "));
+        }
+        for block in &analysis.unreachable {
+            report += &format!("unreachable: {}\n", self.describe_block(*block));
+        }
+        for members in &analysis.infinite_loops {
+            report += &format!(
+                "infinite loop: {}\n",
+                members.iter().map(|block| self.describe_block(*block)).collect::<Vec<_>>().join(", ")
+            );
+        }
+        for (ip, target) in &analysis.out_of_range {
+            report += &format!("jump out of range: {} ({}) goes to {}\n", ip, self.memory[*ip], target);
+        }
+        report
+    }
+
+    pub fn to_dot(&self) -> String {
+        let analysis = self.analyse();
+        let looping: BTreeSet<usize> = analysis.infinite_loops.iter().flatten().copied().collect();
+        let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+        for (block, contents) in self.blocks.iter().enumerate() {
+            let label: String = (contents.start..contents.end)
+                .map(|ip| format!("{}: {}\\l", ip, self.memory[ip]))
+                .collect();
+            let style = if analysis.unreachable.contains(&block) {
+                ", style=dashed, fontcolor=gray"
+            } else if looping.contains(&block) {
+                ", color=orange"
+            } else {
+                ""
+            };
+            dot += &format!("    b{} [label=\"{}\"{}];\n", block, label, style);
+        }
+        dot += "    exit [shape=doublecircle];\n";
+        for (block, contents) in self.blocks.iter().enumerate() {
+            for edge in &contents.successors {
+                match edge {
+                    Edge::Block(next) => dot += &format!("    b{} -> b{};\n", block, next),
+                    Edge::Exit => dot += &format!("    b{} -> exit;\n", block),
+                    Edge::OutOfRange(address) => {
+                        dot += &format!("    oob{} [label=\"{}\", shape=octagon, color=red];\n", block, address);
+                        dot += &format!("    b{} -> oob{} [color=red];\n", block, block);
+                    }
+                }
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_program;
+    use crate::test_support::EXAMPLE;
+
+    #[test]
+    fn test_basic_blocks() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let cfg = ControlFlowGraph::new(&program);
+        let ranges: Vec<(usize, usize)> = cfg.blocks.iter().map(|block| (block.start, block.end)).collect();
+        assert_eq!(ranges, vec![(0, 1), (1, 3), (3, 5), (5, 6), (6, 8), (8, 9)]);
+        assert_eq!(cfg.blocks[1].successors, vec![Edge::Block(4)]);
+        assert_eq!(cfg.blocks[2].successors, vec![Edge::Block(1)]);
+        assert_eq!(cfg.blocks[5].successors, vec![Edge::Exit]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_analysis() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        assert_eq!(ControlFlowGraph::new(&program).analyse(), Analysis {
+            unreachable: vec![3, 5],
+            infinite_loops: vec![vec![1, 2, 4]],
+            out_of_range: vec![],
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn test_halting_program() -> Result<(), String> {
+        let program = parse_program("acc +1\njmp +2\nacc +5\nnop +0")?;
+        let analysis = ControlFlowGraph::new(&program).analyse();
+        assert_eq!(analysis.unreachable, vec![1]);
+        assert!(analysis.infinite_loops.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_loop_and_out_of_range() -> Result<(), String> {
+        let program = parse_program("nop +0\njmp +0\njmp +7\njmp -4")?;
+        let cfg = ControlFlowGraph::new(&program);
+        let analysis = cfg.analyse();
+        assert_eq!(analysis.infinite_loops, vec![vec![1]]);
+        assert_eq!(analysis.unreachable, vec![2, 3]);
+        assert_eq!(analysis.out_of_range, vec![(2, 9), (3, -1)]);
+        assert_eq!(cfg.blocks[3].successors, vec![Edge::OutOfRange(-1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_loop_ending_out_of_range() -> Result<(), String> {
+        let program = parse_program("acc b +3\nacc b -1\njnz b -1\njmp -10")?;
+        let analysis = ControlFlowGraph::new(&program).analyse();
+        // The loop stops the program by jumping out of it, so it is not infinite
+        assert!(analysis.infinite_loops.is_empty());
+        assert_eq!(analysis.out_of_range, vec![(3, -7)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditional_jumps() -> Result<(), String> {
+        let program = parse_program("acc b +3\nacc -1\njnz b -1\nhlt\nout a\njz a +0")?;
+        let cfg = ControlFlowGraph::new(&program);
+        let ranges: Vec<(usize, usize)> = cfg.blocks.iter().map(|block| (block.start, block.end)).collect();
+        assert_eq!(ranges, vec![(0, 1), (1, 3), (3, 4), (4, 5), (5, 6)]);
+        assert_eq!(cfg.blocks[1].successors, vec![Edge::Block(2), Edge::Block(1)]);
+        assert_eq!(cfg.blocks[2].successors, vec![Edge::Exit]);
+        let analysis = cfg.analyse();
+        // The loop on block 1 can exit whichever way b goes, so it is not reported
+        assert!(analysis.infinite_loops.is_empty());
+        assert_eq!(analysis.unreachable, vec![3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_report() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let report = ControlFlowGraph::new(&program).report();
+        assert!(report.starts_with("9 instructions in 6 basic blocks\n  block 0 (0) -> block 1\n"));
+        assert!(report.contains("unreachable: block 3 (5)\n"));
+        assert!(report.contains("infinite loop: block 1 (1-2), block 2 (3-4), block 4 (6-7)\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot() -> Result<(), String> {
+        let program = parse_program("acc +1\njmp -5\nacc +2")?;
+        let dot = ControlFlowGraph::new(&program).to_dot();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    b0 [label=\"0: acc +1\\l1: jmp -5\\l\"];\n"));
+        assert!(dot.contains("    b0 -> oob0 [color=red];\n"));
+        assert!(dot.contains("    b1 -> exit;\n"));
+        assert!(dot.contains("    b1 [label=\"2: acc +2\\l\", style=dashed, fontcolor=gray];\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_program() {
+        let cfg = ControlFlowGraph::new(&[]);
+        assert!(cfg.blocks.is_empty());
+        assert_eq!(cfg.analyse().unreachable, Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use crate::emulator::{LoopDetector, Machine, RunOutcome, State};
+use crate::snapshot::MachineSnapshot;
+use crate::instruction::Instruction;
+use crate::instruction::operation::Operation;
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum Breakpoint {
+    Address(u32),
+    Opcode(Operation),
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum Watchpoint {
+    // Stop whenever the accumulator changes
+    Changed,
+    // Stop when the accumulator becomes this value
+    Equals(i32),
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum Command {
+    Step(usize),
+    Continue,
+    Back(usize),
+    Break(Breakpoint),
+    Delete(Breakpoint),
+    ListBreakpoints,
+    Watch(Watchpoint),
+    Unwatch,
+    Print,
+    Disassemble(u32),
+    Reset,
+    Help,
+    Quit,
+}
+
+fn parse_breakpoint(s: &str) -> Result<Breakpoint, String> {
+    match s.parse::<Operation>() {
+        Ok(op) => Ok(Breakpoint::Opcode(op)),
+        Err(_) => s
+            .parse()
+            .map(Breakpoint::Address)
+            .map_err(|_| format!("Expected an address or opcode, found '{}'", s)),
+    }
+}
+
+fn parse_count<T: FromStr>(s: Option<&&str>, default: T) -> Result<T, String> {
+    s.map_or(Ok(default), |s| s.parse().map_err(|_| format!("Expected a count, found '{}'", s)))
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["step", rest @ ..] | ["s", rest @ ..] if rest.len() <= 1 => Ok(Command::Step(parse_count(rest.first(), 1)?)),
+            ["continue"] | ["c"] => Ok(Command::Continue),
+            ["back", rest @ ..] | ["b", rest @ ..] if rest.len() <= 1 => Ok(Command::Back(parse_count(rest.first(), 1)?)),
+            ["break"] => Ok(Command::ListBreakpoints),
+            ["break", target] => Ok(Command::Break(parse_breakpoint(target)?)),
+            ["delete", target] => Ok(Command::Delete(parse_breakpoint(target)?)),
+            ["watch"] | ["watch", "acc"] => Ok(Command::Watch(Watchpoint::Changed)),
+            ["watch", "acc", "==", value] | ["watch", value] => value
+                .parse()
+                .map(|value| Command::Watch(Watchpoint::Equals(value)))
+                .map_err(|_| format!("Expected an accumulator value, found '{}'", value)),
+            ["unwatch"] => Ok(Command::Unwatch),
+            ["print"] | ["print", "state"] | ["p"] => Ok(Command::Print),
+            ["disassemble", rest @ ..] | ["dis", rest @ ..] if rest.len() <= 1 => Ok(Command::Disassemble(parse_count(rest.first(), 3)?)),
+            ["reset"] => Ok(Command::Reset),
+            ["help"] | ["h"] => Ok(Command::Help),
+            ["quit"] | ["q"] => Ok(Command::Quit),
+            _ => Err(format!("Unknown command '{}', try 'help'", s.trim())),
+        }
+    }
+}
+
+const HELP: &str = "step [n]            run n instructions (default 1)
+continue            run until a breakpoint, watchpoint, loop or the program stops, or for
+                    at most a million steps when the program uses jz or jnz
+back [n]            undo the last n instructions (default 1)
+break [addr|op]     stop before an address or opcode, or list breakpoints
+delete <addr|op>    remove a breakpoint
+watch [acc [== n]]  stop when the accumulator changes, or becomes n
+unwatch             remove the watchpoint
+print state         show the instruction pointer and accumulator
+disassemble [n]     show n instructions either side of the instruction pointer
+reset               start the program again
+quit";
+
+// Enough to put the machine back as it was before an instruction ran. Output only ever
+// grows, so its length stands in for it.
+struct Checkpoint {
+    state: State,
+    output_length: usize,
+    steps: usize,
+}
+
+pub struct Debugger<'a> {
+    machine: Machine<'a>,
+    // The machine before every instruction run so far, for stepping backwards
+    history: Vec<Checkpoint>,
+    breakpoints: BTreeSet<u32>,
+    opcode_breakpoints: Vec<Operation>,
+    watchpoint: Option<Watchpoint>,
+    outcome: Option<RunOutcome>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(memory: &'a [Instruction]) -> Debugger<'a> {
+        Debugger {
+            machine: Machine::new(memory),
+            history: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            opcode_breakpoints: Vec::new(),
+            watchpoint: None,
+            outcome: None,
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.machine.state
+    }
+
+    fn current_instruction(&self) -> Option<&'a Instruction> {
+        self.machine.memory().get(self.machine.state.instruction_pointer as usize)
+    }
+
+    fn describe_state(&self) -> String {
+        let mut description = format!(
+            "ip {}, acc {}, {} steps",
+            self.machine.state.instruction_pointer,
+            self.machine.state.value,
+            self.machine.steps()
+        );
+        match (self.outcome, self.current_instruction()) {
+            (Some(outcome), _) => description += &format!("\n{}", describe_outcome(outcome)),
+            (None, Some(instruction)) => description += &format!("\n{:>5}: {}", self.machine.state.instruction_pointer, instruction),
+            (None, None) => {}
+        }
+        description
+    }
+
+    fn is_breakpoint(&self) -> bool {
+        self.breakpoints.contains(&self.machine.state.instruction_pointer)
+            || self.current_instruction().is_some_and(|instruction| self.opcode_breakpoints.contains(&instruction.op))
+    }
+
+    fn watch_triggered(&self, before: i32) -> bool {
+        match self.watchpoint {
+            Some(Watchpoint::Changed) => self.machine.state.value != before,
+            Some(Watchpoint::Equals(value)) => self.machine.state.value == value && before != value,
+            None => false,
+        }
+    }
+
+    // Runs one instruction, recording the machine before it so it can be undone. Nothing is
+    // recorded when no instruction runs, as at the end of the program or on a jump out of it.
+    fn step_once(&mut self) -> Option<RunOutcome> {
+        if let Some(outcome) = self.outcome {
+            return Some(outcome);
+        }
+        let checkpoint = Checkpoint {
+            state: self.machine.state,
+            output_length: self.machine.output.len(),
+            steps: self.machine.steps(),
+        };
+        self.outcome = self.machine.step();
+        if self.machine.steps() != checkpoint.steps {
+            self.history.push(checkpoint);
+        }
+        self.outcome
+    }
+
+    fn rewind(&mut self, checkpoint: Checkpoint) {
+        let memory = self.machine.memory();
+        let mut output = std::mem::take(&mut self.machine.output);
+        output.truncate(checkpoint.output_length);
+        let snapshot = MachineSnapshot {
+            program_length: memory.len(),
+            state: checkpoint.state,
+            output,
+            steps: checkpoint.steps,
+            visited: LoopDetector::new(memory),
+        };
+        self.machine = Machine::restore(memory, snapshot).unwrap();
+        self.outcome = None;
+    }
+
+    fn step(&mut self, count: usize) -> String {
+        for _ in 0..count {
+            let before = self.machine.state.value;
+            if self.step_once().is_some() {
+                break;
+            }
+            if self.watch_triggered(before) {
+                return format!("watchpoint: acc {} -> {}\n{}", before, self.machine.state.value, self.describe_state());
+            }
+        }
+        self.describe_state()
+    }
+
+    fn run_to_stop(&mut self) -> String {
+        let mut loop_detector = LoopDetector::new(self.machine.memory());
+        for _ in 0..loop_detector.step_limit().unwrap_or(usize::MAX) {
+            let before = self.machine.state.value;
+            if !loop_detector.visit(&self.machine.state) {
+                return format!(
+                    "{}\n{}",
+                    describe_outcome(RunOutcome::InfiniteLoop { at: self.machine.state.instruction_pointer }),
+                    self.describe_state()
+                );
+            }
+            if self.step_once().is_some() {
+                return self.describe_state();
+            }
+            if self.watch_triggered(before) {
+                return format!("watchpoint: acc {} -> {}\n{}", before, self.machine.state.value, self.describe_state());
+            }
+            if self.is_breakpoint() {
+                return format!("breakpoint\n{}", self.describe_state());
+            }
+        }
+        format!("{}\n{}", describe_outcome(RunOutcome::StepLimitExceeded), self.describe_state())
+    }
+
+    fn back(&mut self, count: usize) -> String {
+        for _ in 0..count {
+            match self.history.pop() {
+                Some(checkpoint) => self.rewind(checkpoint),
+                None => return format!("at the start of the program\n{}", self.describe_state()),
+            }
+        }
+        self.describe_state()
+    }
+
+    fn disassemble(&self, radius: u32) -> String {
+        let ip = self.machine.state.instruction_pointer;
+        let memory = self.machine.memory();
+        let start = ip.saturating_sub(radius) as usize;
+        let end = ((ip as usize).saturating_add(radius as usize + 1)).min(memory.len());
+        (start..end)
+            .map(|address| format!(
+                "{}{}{:>4}: {}",
+                if address == ip as usize { "=>" } else { "  " },
+                if self.breakpoints.contains(&(address as u32)) { "*" } else { " " },
+                address,
+                memory[address]
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn list_breakpoints(&self) -> String {
+        let mut lines: Vec<String> = self.breakpoints.iter().map(|address| format!("address {}", address)).collect();
+        lines.extend(self.opcode_breakpoints.iter().map(|op| format!("opcode {}", op)));
+        if let Some(watchpoint) = &self.watchpoint {
+            lines.push(match watchpoint {
+                Watchpoint::Changed => "watch acc".to_string(),
+                Watchpoint::Equals(value) => format!("watch acc == {}", value),
+            });
+        }
+        if lines.is_empty() {
+            "no breakpoints".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+
+    // Runs a command, returning the text to show the user
+    pub fn execute(&mut self, command: Command) -> String {
+        match command {
+            Command::Step(count) => self.step(count),
+            Command::Continue => self.run_to_stop(),
+            Command::Back(count) => self.back(count),
+            Command::Break(Breakpoint::Address(address)) => {
+                self.breakpoints.insert(address);
+                format!("breakpoint at address {}", address)
+            }
+            Command::Break(Breakpoint::Opcode(op)) => {
+                if !self.opcode_breakpoints.contains(&op) {
+                    self.opcode_breakpoints.push(op);
+                }
+                format!("breakpoint on opcode {}", op)
+            }
+            Command::Delete(Breakpoint::Address(address)) => {
+                self.breakpoints.remove(&address);
+                format!("removed breakpoint at address {}", address)
+            }
+            Command::Delete(Breakpoint::Opcode(op)) => {
+                self.opcode_breakpoints.retain(|existing| *existing != op);
+                format!("removed breakpoint on opcode {}", op)
+            }
+            Command::ListBreakpoints => self.list_breakpoints(),
+            Command::Watch(watchpoint) => {
+                self.watchpoint = Some(watchpoint);
+                self.list_breakpoints()
+            }
+            Command::Unwatch => {
+                self.watchpoint = None;
+                "removed watchpoint".to_string()
+            }
+            Command::Print => self.describe_state(),
+            Command::Disassemble(radius) => self.disassemble(radius),
+            Command::Reset => {
+                self.machine = Machine::new(self.machine.memory());
+                self.history.clear();
+                self.outcome = None;
+                self.describe_state()
+            }
+            Command::Help => HELP.to_string(),
+            Command::Quit => String::new(),
+        }
+    }
+}
+
+fn describe_outcome(outcome: RunOutcome) -> String {
+    match outcome {
+        RunOutcome::Halted => "program halted".to_string(),
+        RunOutcome::InfiniteLoop { at } => format!("infinite loop: instruction {} is about to run again", at),
+        RunOutcome::JumpedOutOfBounds { from, to } => format!("instruction {} jumped out of bounds to {}", from, to),
+        RunOutcome::StepLimitExceeded => "step limit exceeded".to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_program;
+    use crate::test_support::EXAMPLE;
+
+    fn run(debugger: &mut Debugger, command: &str) -> String {
+        debugger.execute(command.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_commands() -> Result<(), String> {
+        assert_eq!("step".parse::<Command>()?, Command::Step(1));
+        assert_eq!("s 5".parse::<Command>()?, Command::Step(5));
+        assert_eq!("break 4".parse::<Command>()?, Command::Break(Breakpoint::Address(4)));
+        assert_eq!("break jmp".parse::<Command>()?, Command::Break(Breakpoint::Opcode(Operation::Jump)));
+        assert_eq!("watch acc == -3".parse::<Command>()?, Command::Watch(Watchpoint::Equals(-3)));
+        assert_eq!("watch".parse::<Command>()?, Command::Watch(Watchpoint::Changed));
+        assert_eq!("print state".parse::<Command>()?, Command::Print);
+        assert_eq!("back 2".parse::<Command>()?, Command::Back(2));
+        assert!("break here".parse::<Command>().is_err());
+        assert!("jump".parse::<Command>().is_err());
+        assert_eq!("dis 4".parse::<Command>()?, Command::Disassemble(4));
+        assert_eq!("dis 4294967296".parse::<Command>().err().unwrap(), "Expected a count, found '4294967296'");
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_and_back() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let mut debugger = Debugger::new(&program);
+        run(&mut debugger, "step 3");
+        assert_eq!(debugger.state().instruction_pointer, 6);
+        assert_eq!(debugger.state().value, 1);
+        run(&mut debugger, "step");
+        assert_eq!(debugger.state().value, 2);
+        assert_eq!(run(&mut debugger, "back 2"), "ip 2, acc 1, 2 steps\n    2: jmp +4");
+        run(&mut debugger, "back 5");
+        assert_eq!(*debugger.state(), State::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_continue_to_loop() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let mut debugger = Debugger::new(&program);
+        let output = run(&mut debugger, "continue");
+        assert!(output.starts_with("infinite loop: instruction 1 is about to run again"));
+        assert_eq!(debugger.state().value, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_continue_stops_at_step_limit() -> Result<(), String> {
+        let program = parse_program("acc b +1\njnz b -1")?;
+        let mut debugger = Debugger::new(&program);
+        assert!(run(&mut debugger, "c").starts_with("step limit exceeded\nip 0, acc 0, 1000000 steps"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpoints() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let mut debugger = Debugger::new(&program);
+        run(&mut debugger, "break 4");
+        assert!(run(&mut debugger, "c").starts_with("breakpoint\nip 4, acc 5"));
+        run(&mut debugger, "delete 4");
+        run(&mut debugger, "reset");
+        run(&mut debugger, "break jmp");
+        assert!(run(&mut debugger, "c").starts_with("breakpoint\nip 2, acc 1"));
+        assert!(run(&mut debugger, "c").starts_with("breakpoint\nip 7, acc 2"));
+        assert_eq!(run(&mut debugger, "break"), "opcode jmp");
+        Ok(())
+    }
+
+    #[test]
+    fn test_watchpoints() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let mut debugger = Debugger::new(&program);
+        run(&mut debugger, "watch acc == 5");
+        assert!(run(&mut debugger, "c").starts_with("watchpoint: acc 2 -> 5\nip 4"));
+        run(&mut debugger, "reset");
+        run(&mut debugger, "watch");
+        assert!(run(&mut debugger, "c").starts_with("watchpoint: acc 0 -> 1\nip 2"));
+        assert!(run(&mut debugger, "step 10").starts_with("watchpoint: acc 1 -> 2\nip 7"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_halt_and_back() -> Result<(), String> {
+        let program = parse_program("acc +1\njmp +1\nacc +2")?;
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(run(&mut debugger, "c"), "ip 3, acc 3, 3 steps\nprogram halted");
+        assert_eq!(run(&mut debugger, "step"), "ip 3, acc 3, 3 steps\nprogram halted");
+        assert_eq!(run(&mut debugger, "back"), "ip 2, acc 1, 2 steps\n    2: acc +2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_back_restores_everything() -> Result<(), String> {
+        let program = parse_program("jmp +1\nout a\njmp +0")?;
+        let mut debugger = Debugger::new(&program);
+        run(&mut debugger, "step 4");
+        assert_eq!(debugger.machine.output, vec![0]);
+        // jmp +0 leaves the state as it was, but is still a step to undo
+        assert_eq!(run(&mut debugger, "back"), "ip 2, acc 0, 3 steps\n    2: jmp +0");
+        assert_eq!(run(&mut debugger, "back 2"), "ip 1, acc 0, 1 steps\n    1: out a");
+        assert!(debugger.machine.output.is_empty());
+        run(&mut debugger, "step 2");
+        run(&mut debugger, "reset");
+        assert!(debugger.machine.output.is_empty());
+        assert_eq!(debugger.machine.steps(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_out_of_bounds() -> Result<(), String> {
+        let program = parse_program("acc +1\njmp -5")?;
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(run(&mut debugger, "c"), "ip 1, acc 1, 1 steps\ninstruction 1 jumped out of bounds to -4");
+        Ok(())
+    }
+
+    #[test]
+    fn test_disassemble() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let mut debugger = Debugger::new(&program);
+        run(&mut debugger, "break 3");
+        run(&mut debugger, "step 2");
+        assert_eq!(run(&mut debugger, "dis 1"), "      1: acc +1\n=>    2: jmp +4\n  *   3: acc +3");
+        assert_eq!(run(&mut debugger, "reset"), "ip 0, acc 0, 0 steps\n    0: nop +0");
+        assert_eq!(run(&mut debugger, "dis 1"), "=>    0: nop +0\n      1: acc +1");
+        Ok(())
+    }
+}
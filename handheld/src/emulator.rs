@@ -0,0 +1,425 @@
+use crate::instruction::Instruction;
+use crate::instruction::operation::Operation;
+use crate::instruction::register::Register;
+use crate::snapshot::MachineSnapshot;
+use crate::trace::{Trace, TraceEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// `value` is the accumulator, register a. Registers wrap around on overflow.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct State {
+    pub value: i32,
+    pub instruction_pointer: u32,
+    pub halted: bool,
+    // Registers b, c and d
+    pub registers: [i32; 3],
+}
+
+impl State {
+    pub fn new() -> State {
+        State {
+            value: 0,
+            instruction_pointer: 0,
+            halted: false,
+            registers: [0; 3],
+        }
+    }
+
+    pub fn register(&self, register: Register) -> i32 {
+        match register {
+            Register::A => self.value,
+            other => self.registers[other.index() - 1],
+        }
+    }
+
+    pub fn set_register(&mut self, register: Register, value: i32) {
+        match register {
+            Register::A => self.value = value,
+            other => self.registers[other.index() - 1] = value,
+        }
+    }
+}
+
+impl Default for State {
+    fn default() -> State {
+        State::new()
+    }
+}
+
+// Every way a program can stop running
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub enum RunOutcome {
+    // The instruction pointer moved to just past the last instruction, or hlt ran
+    Halted,
+    // The instruction at `at` was about to run for the second time (or, when a jump depends
+    // on a register, about to run again from exactly the same state)
+    InfiniteLoop { at: u32 },
+    // The instruction at `from` moved the instruction pointer anywhere else outside of memory
+    JumpedOutOfBounds { from: u32, to: i64 },
+    StepLimitExceeded,
+}
+
+fn run_instruction(
+    s: State,
+    memory: &[Instruction],
+) -> Result<State, RunOutcome> {
+    let next_ins = &memory[s.instruction_pointer as usize];
+    let mut next = s;
+    let register = s.register(next_ins.register);
+    let offset = match next_ins.op {
+        Operation::Accumulate => {
+            next.set_register(next_ins.register, register.wrapping_add(next_ins.arg));
+            1
+        }
+        Operation::Multiply => {
+            next.set_register(next_ins.register, register.wrapping_mul(next_ins.arg));
+            1
+        }
+        Operation::Jump => next_ins.arg as i64,
+        Operation::JumpIfZero if register == 0 => next_ins.arg as i64,
+        Operation::JumpIfNotZero if register != 0 => next_ins.arg as i64,
+        Operation::Halt => {
+            next.halted = true;
+            return Ok(next);
+        }
+        Operation::JumpIfZero | Operation::JumpIfNotZero | Operation::NoOperation | Operation::Output => 1,
+    };
+    let next_pointer = s.instruction_pointer as i64 + offset;
+    if next_pointer < 0 || next_pointer > memory.len() as i64 {
+        return Err(RunOutcome::JumpedOutOfBounds { from: s.instruction_pointer, to: next_pointer });
+    }
+    next.instruction_pointer = next_pointer as u32;
+    next.halted = next_pointer as usize == memory.len();
+    Ok(next)
+}
+
+// How many instructions `Machine::run` runs, when not given a limit, on a program using jz or
+// jnz. A loop through those is only spotted once the whole state repeats, which can take
+// billions of steps.
+pub const DEFAULT_STEP_LIMIT: usize = 1_000_000;
+
+// Spots a program coming back to somewhere it has already been. Without jz or jnz, the
+// instruction pointer alone decides what runs next, so a repeated address means the program
+// will loop forever. With them, the whole state has to repeat.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct LoopDetector {
+    by_address: bool,
+    addresses: HashSet<u32>,
+    states: HashSet<State>,
+}
+
+impl LoopDetector {
+    pub fn new(memory: &[Instruction]) -> LoopDetector {
+        LoopDetector {
+            by_address: !memory.iter().any(|instruction| instruction.op.is_conditional()),
+            addresses: HashSet::new(),
+            states: HashSet::new(),
+        }
+    }
+
+    // False if the program has been here before
+    pub fn visit(&mut self, state: &State) -> bool {
+        if self.by_address {
+            self.addresses.insert(state.instruction_pointer)
+        } else {
+            self.states.insert(*state)
+        }
+    }
+
+    // How far to run before giving up on spotting a loop. Tracking addresses needs no limit,
+    // as some address repeats within one more step than there are instructions.
+    pub fn step_limit(&self) -> Option<usize> {
+        if self.by_address { None } else { Some(DEFAULT_STEP_LIMIT) }
+    }
+
+    pub fn clear(&mut self) {
+        self.addresses.clear();
+        self.states.clear();
+    }
+}
+
+pub struct Machine<'a> {
+    memory: &'a [Instruction],
+    pub state: State,
+    // Every value written by out, in order
+    pub output: Vec<i32>,
+    trace: Option<Trace>,
+    // Where `run` has been, kept between calls so a run can be resumed
+    loop_detector: LoopDetector,
+    // Instructions executed since the machine started
+    steps: usize,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(memory: &'a [Instruction]) -> Machine<'a> {
+        Machine {
+            memory,
+            state: State::new(),
+            output: Vec::new(),
+            trace: None,
+            loop_detector: LoopDetector::new(memory),
+            steps: 0,
+        }
+    }
+
+    // Picks up a machine where `snapshot` left off, on the program it was taken from or one
+    // of the same length
+    pub fn restore(memory: &'a [Instruction], snapshot: MachineSnapshot) -> Result<Machine<'a>, String> {
+        if snapshot.program_length != memory.len() {
+            return Err(format!(
+                "Snapshot was taken from a program of {} instructions, not {}",
+                snapshot.program_length,
+                memory.len(),
+            ));
+        }
+        if snapshot.state.instruction_pointer as usize > memory.len() {
+            return Err(format!("Snapshot instruction pointer {} is outside the program", snapshot.state.instruction_pointer));
+        }
+        Ok(Machine {
+            memory,
+            state: snapshot.state,
+            output: snapshot.output,
+            trace: None,
+            loop_detector: snapshot.visited,
+            steps: snapshot.steps,
+        })
+    }
+
+    pub fn snapshot(&self) -> MachineSnapshot {
+        MachineSnapshot {
+            program_length: self.memory.len(),
+            state: self.state,
+            output: self.output.clone(),
+            steps: self.steps,
+            visited: self.loop_detector.clone(),
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // Records every instruction this machine executes
+    pub fn with_trace(mut self) -> Machine<'a> {
+        self.trace = Some(Trace::new());
+        self
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    pub fn memory(&self) -> &'a [Instruction] {
+        self.memory
+    }
+
+    // Runs the instruction at the instruction pointer, returning why the machine cannot
+    // continue if it has stopped. A jump out of bounds leaves the state as it was before the jump.
+    pub fn step(&mut self) -> Option<RunOutcome> {
+        if self.state.halted || self.state.instruction_pointer as usize == self.memory.len() {
+            self.state.halted = true;
+            return Some(RunOutcome::Halted);
+        }
+        let instruction = self.memory[self.state.instruction_pointer as usize];
+        match run_instruction(self.state, self.memory) {
+            Ok(state) => {
+                if instruction.op == Operation::Output {
+                    self.output.push(self.state.register(instruction.register));
+                }
+                if let Some(trace) = &mut self.trace {
+                    trace.record(TraceEntry {
+                        ip: self.state.instruction_pointer,
+                        instruction,
+                        acc_before: self.state.value,
+                        acc_after: state.value,
+                    });
+                }
+                self.state = state;
+                self.steps += 1;
+                if state.halted { Some(RunOutcome::Halted) } else { None }
+            }
+            Err(outcome) => Some(outcome),
+        }
+    }
+
+    // Steps until the program stops, or an instruction is about to run twice, or
+    // `step_limit` more instructions have run. Without a limit, a program using jz or jnz
+    // stops after `DEFAULT_STEP_LIMIT`. Instructions run by earlier calls count as having run
+    // before, so a run stopped by the step limit can carry on where it left off.
+    pub fn run(&mut self, step_limit: Option<usize>) -> RunOutcome {
+        let step_limit = step_limit.or_else(|| self.loop_detector.step_limit()).unwrap_or(usize::MAX);
+        let mut steps = 0;
+        loop {
+            if self.state.halted {
+                return RunOutcome::Halted;
+            }
+            if steps >= step_limit {
+                return RunOutcome::StepLimitExceeded;
+            }
+            steps += 1;
+            if !self.loop_detector.visit(&self.state) {
+                return RunOutcome::InfiniteLoop { at: self.state.instruction_pointer };
+            }
+            if let Some(outcome) = self.step() {
+                return outcome;
+            }
+        }
+    }
+
+    // Runs up to `step_limit` instructions without stopping for loops. Where it went is still
+    // remembered for any later `run`.
+    pub fn run_steps(&mut self, step_limit: usize) -> RunOutcome {
+        for _ in 0..step_limit {
+            if !self.state.halted {
+                self.loop_detector.visit(&self.state);
+            }
+            if let Some(outcome) = self.step() {
+                return outcome;
+            }
+        }
+        if self.state.halted { RunOutcome::Halted } else { RunOutcome::StepLimitExceeded }
+    }
+}
+
+pub fn run_until_repeat(
+    memory: &[Instruction],
+) -> i32 {
+    run_until_repeat_or_halt(memory).1.value
+}
+
+pub fn run_until_repeat_or_halt(
+    memory: &[Instruction],
+) -> (RunOutcome, State) {
+    let mut machine = Machine::new(memory);
+    let outcome = machine.run(None);
+    (outcome, machine.state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::EXAMPLE;
+
+    fn parse(s: &str) -> Vec<Instruction> {
+        s.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let instructions = parse(EXAMPLE);
+        assert_eq!(run_until_repeat(&instructions), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_halts() {
+        let instructions = parse(&EXAMPLE.replace("jmp -4", "nop -4"));
+        let (outcome, state) = run_until_repeat_or_halt(&instructions);
+        assert_eq!(outcome, RunOutcome::Halted);
+        assert!(state.halted);
+        assert_eq!(state.value, 8);
+    }
+
+    #[test]
+    fn test_run_outcomes() {
+        let looping = parse(EXAMPLE);
+        assert_eq!(Machine::new(&looping).run(None), RunOutcome::InfiniteLoop { at: 1 });
+        assert_eq!(Machine::new(&looping).run(Some(3)), RunOutcome::StepLimitExceeded);
+        let halting = parse("acc +1\njmp +2\nacc +5");
+        assert_eq!(Machine::new(&halting).run(None), RunOutcome::Halted);
+        assert_eq!(Machine::new(&[]).run(None), RunOutcome::Halted);
+    }
+
+    #[test]
+    fn test_jumped_out_of_bounds() {
+        let escaping = parse("acc +1\njmp +5\nacc +5");
+        let mut machine = Machine::new(&escaping);
+        assert_eq!(machine.run(None), RunOutcome::JumpedOutOfBounds { from: 1, to: 6 });
+        assert_eq!(machine.state.instruction_pointer, 1);
+        assert_eq!(machine.state.value, 1);
+        let escaping_backwards = parse("acc +1\njmp -2\nacc +5");
+        assert_eq!(Machine::new(&escaping_backwards).run(None), RunOutcome::JumpedOutOfBounds { from: 1, to: -1 });
+        let far_backwards = parse("jmp -2147483648");
+        assert_eq!(Machine::new(&far_backwards).run(None), RunOutcome::JumpedOutOfBounds { from: 0, to: -2147483648 });
+    }
+
+    #[test]
+    fn test_registers_and_output() {
+        // Counts b down from 3, doubling a each time
+        let instructions = parse("acc +1\nacc b +3\nmul +2\nout a\nacc b -1\njnz b -3\nout b\nhlt\nacc +100");
+        let mut machine = Machine::new(&instructions);
+        assert_eq!(machine.run(None), RunOutcome::Halted);
+        assert_eq!(machine.state.value, 8);
+        assert_eq!(machine.state.register(Register::B), 0);
+        assert_eq!(machine.state.instruction_pointer, 7);
+        assert_eq!(machine.output, vec![2, 4, 8, 0]);
+    }
+
+    #[test]
+    fn test_conditional_infinite_loop() {
+        let instructions = parse("acc b +1\njnz c +2\njmp -1\nhlt");
+        assert_eq!(Machine::new(&instructions).run(None), RunOutcome::InfiniteLoop { at: 1 });
+        let instructions = parse("jz a +2\nhlt\nacc +1\njmp -3");
+        assert_eq!(Machine::new(&instructions).run(None), RunOutcome::Halted);
+    }
+
+    #[test]
+    fn test_unconditional_extended_programs_loop_by_address() {
+        // mul, out and hlt never decide where to go next, so the first repeated address is a loop
+        let instructions = parse("acc +1\nmul +2\nout a\njmp -3\nhlt");
+        let mut machine = Machine::new(&instructions);
+        assert_eq!(machine.run(None), RunOutcome::InfiniteLoop { at: 0 });
+        assert_eq!(machine.steps(), 4);
+    }
+
+    #[test]
+    fn test_default_step_limit() {
+        // b never comes back round to the same value within the limit
+        let instructions = parse("acc b +1\njnz b -1");
+        let mut machine = Machine::new(&instructions);
+        assert_eq!(machine.run(None), RunOutcome::StepLimitExceeded);
+        assert_eq!(machine.steps(), DEFAULT_STEP_LIMIT);
+    }
+
+    #[test]
+    fn test_no_default_limit_for_original_programs() {
+        let mut instructions = vec![Instruction::new(Operation::NoOperation, 0); DEFAULT_STEP_LIMIT + 100];
+        instructions.push(Instruction::new(Operation::Accumulate, 1));
+        let (outcome, state) = run_until_repeat_or_halt(&instructions);
+        assert_eq!(outcome, RunOutcome::Halted);
+        assert_eq!(state.value, 1);
+    }
+
+    #[test]
+    fn test_registers_wrap() {
+        let instructions = parse("acc +2147483647\nacc +1\nmul d +5");
+        let mut machine = Machine::new(&instructions);
+        assert_eq!(machine.run(None), RunOutcome::Halted);
+        assert_eq!(machine.state.value, i32::MIN);
+    }
+
+    #[test]
+    fn test_step() {
+        let instructions = parse("acc +1\nacc +2");
+        let mut machine = Machine::new(&instructions);
+        assert_eq!(machine.step(), None);
+        assert_eq!(machine.state.value, 1);
+        assert_eq!(machine.step(), Some(RunOutcome::Halted));
+        assert_eq!(machine.state.value, 3);
+        assert_eq!(machine.step(), Some(RunOutcome::Halted));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use crate::emulator::{run_until_repeat_or_halt, Machine, RunOutcome};
+use crate::generator::Termination;
+use crate::instruction::Instruction;
+use crate::instruction::operation::Operation;
+use crate::repair::{brute_force_repair, find_repair, swap_op};
+
+// A loop through a conditional jump is only reported once the whole state repeats, which can
+// take billions of steps, so programs using them are only run this far
+pub const STEP_LIMIT: usize = 10_000;
+
+// Everything visible about how a run ended. Register a is the accumulator.
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Observation {
+    pub outcome: RunOutcome,
+    pub registers: [i32; 4],
+    pub instruction_pointer: u32,
+    pub output: Vec<i32>,
+}
+
+// A deliberately plain interpreter, sharing nothing with the emulator but the instruction
+// types, for the emulator to be checked against
+pub fn reference_run(program: &[Instruction], step_limit: Option<usize>) -> Observation {
+    let by_address = !program.iter().any(|instruction| matches!(instruction.op, Operation::JumpIfZero | Operation::JumpIfNotZero));
+    let mut registers = [0i32; 4];
+    let mut ip: i64 = 0;
+    let mut output = Vec::new();
+    let mut seen: HashSet<(i64, [i32; 4])> = HashSet::new();
+    let mut steps = 0;
+    let outcome = loop {
+        if ip == program.len() as i64 {
+            break RunOutcome::Halted;
+        }
+        if step_limit == Some(steps) {
+            break RunOutcome::StepLimitExceeded;
+        }
+        if !seen.insert((ip, if by_address { [0; 4] } else { registers })) {
+            break RunOutcome::InfiniteLoop { at: ip as u32 };
+        }
+        let instruction = program[ip as usize];
+        let r = instruction.register.index();
+        let mut next = ip + 1;
+        match instruction.op {
+            Operation::Accumulate => registers[r] = registers[r].wrapping_add(instruction.arg),
+            Operation::Multiply => registers[r] = registers[r].wrapping_mul(instruction.arg),
+            Operation::Jump => next = ip + instruction.arg as i64,
+            Operation::JumpIfZero if registers[r] == 0 => next = ip + instruction.arg as i64,
+            Operation::JumpIfNotZero if registers[r] != 0 => next = ip + instruction.arg as i64,
+            Operation::Output => output.push(registers[r]),
+            Operation::Halt => break RunOutcome::Halted,
+            Operation::JumpIfZero | Operation::JumpIfNotZero | Operation::NoOperation => {}
+        }
+        if next < 0 || next > program.len() as i64 {
+            break RunOutcome::JumpedOutOfBounds { from: ip as u32, to: next };
+        }
+        ip = next;
+        steps += 1;
+    };
+    Observation { outcome, registers, instruction_pointer: ip as u32, output }
+}
+
+// Runs `program` on the emulator and checks it against the reference interpreter and the
+// properties it should have. Programs without jz or jnz are also checked against
+// `run_until_repeat_or_halt` and the repair search. `termination` is what the program was
+// generated to do.
+pub fn check_invariants(program: &[Instruction], termination: Termination) -> Result<(), String> {
+    let unconditional = !program.iter().any(|instruction| instruction.op.is_conditional());
+    let step_limit = if unconditional { None } else { Some(STEP_LIMIT) };
+    let mut machine = Machine::new(program).with_trace();
+    let outcome = machine.run(step_limit);
+    let state = machine.state;
+    let observed = Observation {
+        outcome,
+        registers: [state.value, state.registers[0], state.registers[1], state.registers[2]],
+        instruction_pointer: state.instruction_pointer,
+        output: machine.output.clone(),
+    };
+    let expected = reference_run(program, step_limit);
+    if observed != expected {
+        return Err(format!("emulator ended with {:?}, reference with {:?}", observed, expected));
+    }
+    match (termination, outcome) {
+        (Termination::Halts, RunOutcome::Halted) | (Termination::Either, _) => {}
+        (Termination::Loops, outcome) if outcome != RunOutcome::Halted => {}
+        (termination, outcome) => return Err(format!("generated to be {:?}, but ended with {:?}", termination, outcome)),
+    }
+    if !unconditional {
+        return Ok(());
+    }
+
+    let mut visited = HashSet::new();
+    for entry in &machine.trace().unwrap().entries {
+        if !visited.insert(entry.ip) {
+            return Err(format!("ip {} ran twice before the loop was reported", entry.ip));
+        }
+    }
+    if let RunOutcome::InfiniteLoop { at } = outcome {
+        if !visited.contains(&at) {
+            return Err(format!("loop reported at {}, which never ran", at));
+        }
+    }
+    if run_until_repeat_or_halt(program) != (outcome, state) {
+        return Err(format!("run_until_repeat_or_halt disagrees with Machine::run, which gave {:?}", outcome));
+    }
+    let repair = find_repair(program);
+    if repair != brute_force_repair(program) {
+        return Err(format!("find_repair gave {:?}, brute force {:?}", repair, brute_force_repair(program)));
+    }
+    if let Some(repair) = repair {
+        let mut repaired = program.to_vec();
+        repaired[repair.index] = swap_op(&repaired[repair.index]);
+        let result = reference_run(&repaired, None);
+        if result.outcome != RunOutcome::Halted || result.registers[0] != repair.value {
+            return Err(format!("{:?} does not halt with that value, the reference gave {:?}", repair, result));
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Generator, Rng};
+    use crate::instruction::parse_program;
+    use crate::test_support::EXAMPLE;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let reference = reference_run(&program, None);
+        assert_eq!(reference.outcome, RunOutcome::InfiniteLoop { at: 1 });
+        assert_eq!(reference.registers[0], 5);
+        check_invariants(&program, Termination::Loops)
+    }
+
+    #[test]
+    fn test_catches_wrong_termination() -> Result<(), String> {
+        let program = parse_program("acc +1")?;
+        assert_eq!(check_invariants(&program, Termination::Loops).err().unwrap(), "generated to be Loops, but ended with Halted");
+        Ok(())
+    }
+
+    fn termination() -> impl Strategy<Value = Termination> {
+        prop_oneof![Just(Termination::Halts), Just(Termination::Loops), Just(Termination::Either)]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+        #[test]
+        fn prop_original_invariants(seed in any::<u64>(), length in 1..60usize, termination in termination()) {
+            let program = Generator::new(length).with_termination(termination).generate(&mut Rng::new(seed));
+            prop_assert_eq!(check_invariants(&program, termination), Ok(()));
+        }
+
+        #[test]
+        fn prop_extended_invariants(seed in any::<u64>(), length in 1..30usize, termination in termination()) {
+            let program = Generator::new(length)
+                .with_termination(termination)
+                .with_extended_operations()
+                .with_max_arg(3)
+                .generate(&mut Rng::new(seed));
+            prop_assert_eq!(check_invariants(&program, termination), Ok(()));
+        }
+    }
+}
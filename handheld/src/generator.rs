@@ -0,0 +1,225 @@
+use std::str::FromStr;
+use crate::instruction::Instruction;
+use crate::instruction::operation::{Operation, RegisterOperand, OPERATIONS};
+use crate::instruction::register::{Register, REGISTERS};
+
+// xorshift64*, so generated programs only depend on the seed
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Zero is a fixed point of xorshift
+        match seed ^ 0x9e3779b97f4a7c15 {
+            0 => Rng(1),
+            state => Rng(state),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    // Uniform in `0..n`, for n > 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    // Uniform in `low..=high`
+    pub fn between(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next_u64() % (high - low + 1) as u64) as i64
+    }
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub enum Termination {
+    // Runs off the end of the program, or reaches hlt
+    Halts,
+    // Never halts
+    Loops,
+    // Whatever the random instructions happen to do
+    Either,
+}
+
+impl FromStr for Termination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "halts" => Ok(Termination::Halts),
+            "loops" => Ok(Termination::Loops),
+            "either" => Ok(Termination::Either),
+            _ => Err(format!("Unknown termination '{}', expected halts, loops or either", s)),
+        }
+    }
+}
+
+// Makes random programs whose jumps all land inside the program or just past its end.
+//
+// For `Halts` and `Loops`, the path the program takes is chosen first: a random run of
+// distinct addresses starting at 0, each instruction on it leading to the next. The last one
+// leads to the end of the program, or back to an address already on the path. Everything off
+// the path is random, and is never run.
+pub struct Generator {
+    length: usize,
+    termination: Termination,
+    max_arg: i32,
+    extended: bool,
+}
+
+impl Generator {
+    pub fn new(length: usize) -> Generator {
+        Generator { length, termination: Termination::Either, max_arg: 100, extended: false }
+    }
+
+    pub fn with_termination(mut self, termination: Termination) -> Generator {
+        assert!(self.length > 0 || termination != Termination::Loops, "An empty program cannot loop");
+        self.termination = termination;
+        self
+    }
+
+    // The largest magnitude of an acc or mul argument
+    pub fn with_max_arg(mut self, max_arg: i32) -> Generator {
+        assert!(max_arg >= 0, "The largest argument cannot be negative");
+        self.max_arg = max_arg;
+        self
+    }
+
+    // Uses registers and every operation, not just acc, jmp and nop
+    pub fn with_extended_operations(mut self) -> Generator {
+        self.extended = true;
+        self
+    }
+
+    fn register(&self, op: Operation, rng: &mut Rng) -> Register {
+        match op.register_operand() {
+            RegisterOperand::None => Register::A,
+            _ if self.extended => REGISTERS[rng.below(REGISTERS.len())],
+            _ => Register::A,
+        }
+    }
+
+    // `op` at `ip` with random operands, keeping any jump from it in range
+    fn instruction(&self, op: Operation, ip: usize, rng: &mut Rng) -> Instruction {
+        let arg = match op {
+            Operation::Jump | Operation::NoOperation | Operation::JumpIfZero | Operation::JumpIfNotZero => {
+                (rng.between(0, self.length as i64) - ip as i64) as i32
+            }
+            Operation::Accumulate | Operation::Multiply => rng.between(-self.max_arg as i64, self.max_arg as i64) as i32,
+            Operation::Output | Operation::Halt => 0,
+        };
+        Instruction { op, register: self.register(op, rng), arg }
+    }
+
+    fn random_instruction(&self, ip: usize, rng: &mut Rng) -> Instruction {
+        // The original acc, jmp and nop come first
+        let operations = if self.extended { &OPERATIONS[..] } else { &OPERATIONS[..3] };
+        self.instruction(operations[rng.below(operations.len())], ip, rng)
+    }
+
+    // An instruction at `from` which always moves the instruction pointer to `to`
+    fn instruction_to(&self, from: usize, to: usize, rng: &mut Rng) -> Instruction {
+        if to != from + 1 {
+            return Instruction::new(Operation::Jump, to as i32 - from as i32);
+        }
+        let operations: &[Operation] = if self.extended {
+            &[Operation::Accumulate, Operation::NoOperation, Operation::Jump, Operation::Multiply, Operation::Output]
+        } else {
+            &[Operation::Accumulate, Operation::NoOperation, Operation::Jump]
+        };
+        match operations[rng.below(operations.len())] {
+            Operation::Jump => Instruction::new(Operation::Jump, 1),
+            op => self.instruction(op, from, rng),
+        }
+    }
+
+    pub fn generate(&self, rng: &mut Rng) -> Vec<Instruction> {
+        let mut program: Vec<Instruction> = (0..self.length).map(|ip| self.random_instruction(ip, rng)).collect();
+        if self.termination == Termination::Either || self.length == 0 {
+            return program;
+        }
+        let mut rest: Vec<usize> = (1..self.length).collect();
+        for i in (1..rest.len()).rev() {
+            rest.swap(i, rng.below(i + 1));
+        }
+        let mut path = vec![0];
+        path.extend_from_slice(&rest[..rng.below(self.length)]);
+        for pair in path.windows(2) {
+            program[pair[0]] = self.instruction_to(pair[0], pair[1], rng);
+        }
+        let last = *path.last().unwrap();
+        program[last] = match self.termination {
+            Termination::Halts if self.extended && rng.below(2) == 0 => Instruction::new(Operation::Halt, 0),
+            Termination::Halts => self.instruction_to(last, self.length, rng),
+            _ => self.instruction_to(last, path[rng.below(path.len())], rng),
+        };
+        program
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // proptest's prelude has an Rng of its own
+    use super::Rng;
+    use crate::emulator::{run_until_repeat_or_halt, RunOutcome};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_same_seed_same_program() {
+        let generator = Generator::new(50).with_extended_operations();
+        assert_eq!(generator.generate(&mut Rng::new(7)), generator.generate(&mut Rng::new(7)));
+        assert_ne!(generator.generate(&mut Rng::new(7)), generator.generate(&mut Rng::new(8)));
+        assert!(Generator::new(0).with_termination(Termination::Halts).generate(&mut Rng::new(0)).is_empty());
+    }
+
+    #[test]
+    fn test_termination_from_str() {
+        assert_eq!("loops".parse::<Termination>(), Ok(Termination::Loops));
+        assert_eq!("sometimes".parse::<Termination>().err().unwrap(), "Unknown termination 'sometimes', expected halts, loops or either");
+    }
+
+    #[test]
+    fn test_max_arg() {
+        let program = Generator::new(50).with_max_arg(0).generate(&mut Rng::new(3));
+        assert!(program.iter().all(|instruction| instruction.op != Operation::Accumulate || instruction.arg == 0));
+        let program = Generator::new(50).with_max_arg(i32::MAX).generate(&mut Rng::new(3));
+        assert!(program.iter().any(|instruction| instruction.op == Operation::Accumulate && instruction.arg.abs() > 100));
+    }
+
+    #[test]
+    #[should_panic(expected = "The largest argument cannot be negative")]
+    fn test_negative_max_arg() {
+        Generator::new(10).with_max_arg(i32::MIN);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_controls_termination(seed in any::<u64>(), length in 1..100usize) {
+            let mut rng = Rng::new(seed);
+            let halts = Generator::new(length).with_termination(Termination::Halts).generate(&mut rng);
+            prop_assert_eq!(run_until_repeat_or_halt(&halts).0, RunOutcome::Halted);
+            let loops = Generator::new(length).with_termination(Termination::Loops).generate(&mut rng);
+            let is_loop = matches!(run_until_repeat_or_halt(&loops).0, RunOutcome::InfiniteLoop { .. });
+            prop_assert!(is_loop);
+        }
+
+        #[test]
+        fn prop_jumps_stay_in_range(seed in any::<u64>(), length in 0..100usize) {
+            let program = Generator::new(length).with_extended_operations().with_max_arg(5).generate(&mut Rng::new(seed));
+            for (ip, instruction) in program.iter().enumerate() {
+                let target = ip as i64 + instruction.arg as i64;
+                match instruction.op {
+                    Operation::Accumulate | Operation::Multiply => prop_assert!(instruction.arg.abs() <= 5),
+                    Operation::Output | Operation::Halt => prop_assert_eq!(instruction.arg, 0),
+                    _ => prop_assert!((0..=length as i64).contains(&target)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+pub mod operation;
+pub mod register;
+
+use std::str::FromStr;
+use std::fmt;
+use crate::instruction::operation::{Operation, RegisterOperand};
+use crate::instruction::register::Register;
+
+
+// `register` is only used by operations which take one, and is the accumulator otherwise
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(Eq, Hash)]
+pub struct Instruction {
+    pub op: Operation,
+    pub register: Register,
+    pub arg: i32,
+}
+
+impl Instruction {
+    pub fn new(op: Operation, arg: i32) -> Instruction {
+        Instruction { op, register: Register::A, arg }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split: Vec<&str> = s.split_whitespace().collect();
+        let (op, mut operands) = match split.split_first() {
+            Some((op, operands)) => (op.parse::<Operation>()?, operands),
+            None => return Err("Expected an operation, found an empty line".to_string()),
+        };
+        let expected = match (op.register_operand(), op.takes_argument()) {
+            (RegisterOperand::None, true) => "an argument",
+            (RegisterOperand::None, false) => "no operands",
+            (RegisterOperand::Optional, true) => "an optional register and an argument",
+            (RegisterOperand::Optional, false) => "an optional register",
+            (RegisterOperand::Required, _) => "a register and an argument",
+        };
+        let wrong_operands = || format!("{} expects {}, found '{}'", op, expected, s);
+
+        let register = match (op.register_operand(), operands.first()) {
+            (RegisterOperand::None, _) => Register::A,
+            (RegisterOperand::Optional, Some(first)) if first.parse::<Register>().is_ok() => {
+                operands = &operands[1..];
+                first.parse()?
+            }
+            (RegisterOperand::Optional, _) => Register::A,
+            (RegisterOperand::Required, Some(first)) => {
+                operands = &operands[1..];
+                first.parse()?
+            }
+            (RegisterOperand::Required, None) => return Err(wrong_operands()),
+        };
+        let arg = match (op.takes_argument(), operands) {
+            (true, [arg]) => arg.parse().map_err(|_| format!("Invalid argument '{}'", arg))?,
+            (false, []) => 0,
+            _ => return Err(wrong_operands()),
+        };
+        Ok(Instruction { op, register, arg })
+    }
+}
+
+// The three original operations print exactly as the boot code writes them
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+        let show_register = match self.op.register_operand() {
+            RegisterOperand::None => false,
+            RegisterOperand::Optional => self.register != Register::A || !self.op.takes_argument(),
+            RegisterOperand::Required => true,
+        };
+        if show_register {
+            write!(f, " {}", self.register)?;
+        }
+        if self.op.takes_argument() {
+            write!(f, " {:+}", self.arg)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_program(s: &str) -> Result<Vec<Instruction>, String> {
+    s
+        .lines()
+        .zip(1..)
+        .map(|(line, line_number)| line.parse().map_err(|e| format!("line {}: {}", line_number, e)))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example_1() -> Result<(), String> {
+        let instruction = "nop +0".parse::<Instruction>()?;
+        assert_eq!(instruction.arg, 0);
+        assert_eq!(instruction.op, Operation::NoOperation);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_2() -> Result<(), String> {
+        let instruction = "acc +1".parse::<Instruction>()?;
+        assert_eq!(instruction.arg, 1);
+        assert_eq!(instruction.op, Operation::Accumulate);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_3() -> Result<(), String> {
+        let instruction = "jmp +4".parse::<Instruction>()?;
+        assert_eq!(instruction.arg, 4);
+        assert_eq!(instruction.op, Operation::Jump);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_4() -> Result<(), String> {
+        let instruction = "jmp -4".parse::<Instruction>()?;
+        assert_eq!(instruction.arg, -4);
+        assert_eq!(instruction.op, Operation::Jump);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<(), String> {
+        for text in ["nop +0", "acc +1", "jmp -4", "acc b +3", "jz c -2", "jnz a +4", "mul +3", "mul d -1", "out a", "out b", "hlt"] {
+            assert_eq!(text.parse::<Instruction>()?.to_string(), text);
+        }
+        assert_eq!("acc a +1".parse::<Instruction>()?.to_string(), "acc +1");
+        assert_eq!("out".parse::<Instruction>()?.to_string(), "out a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_extended_operations() -> Result<(), String> {
+        assert_eq!("acc b -7".parse::<Instruction>()?, Instruction { op: Operation::Accumulate, register: Register::B, arg: -7 });
+        assert_eq!("jnz d +3".parse::<Instruction>()?, Instruction { op: Operation::JumpIfNotZero, register: Register::D, arg: 3 });
+        assert_eq!("mul +2".parse::<Instruction>()?, Instruction::new(Operation::Multiply, 2));
+        assert_eq!("out c".parse::<Instruction>()?, Instruction { op: Operation::Output, register: Register::C, arg: 0 });
+        assert_eq!("hlt".parse::<Instruction>()?, Instruction::new(Operation::Halt, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_program_errors() {
+        assert_eq!(parse_program("nop +0\nacc").err().unwrap(), "line 2: acc expects an optional register and an argument, found 'acc'");
+        assert_eq!(parse_program("nop +0\nacc +x").err().unwrap(), "line 2: Invalid argument '+x'");
+        assert_eq!(parse_program("div +2").err().unwrap(), "line 1: Unknown Operation");
+        assert_eq!(parse_program("jz +2").err().unwrap(), "line 1: Unknown register '+2'");
+        assert_eq!(parse_program("hlt +2").err().unwrap(), "line 1: hlt expects no operands, found 'hlt +2'");
+        assert_eq!(parse_program("acc e +2").err().unwrap(), "line 1: acc expects an optional register and an argument, found 'acc e +2'");
+    }
+}
@@ -0,0 +1,91 @@
+use std::str::FromStr;
+use std::fmt;
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(Eq, Hash)]
+pub enum Operation {
+    Accumulate,
+    Jump,
+    NoOperation,
+    JumpIfZero,
+    JumpIfNotZero,
+    Multiply,
+    Output,
+    Halt,
+}
+
+// Whether an operation names a register before its argument
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum RegisterOperand {
+    None,
+    // Defaults to the accumulator when left out
+    Optional,
+    Required,
+}
+
+pub const OPERATIONS: [Operation; 8] = [
+    Operation::Accumulate,
+    Operation::Jump,
+    Operation::NoOperation,
+    Operation::JumpIfZero,
+    Operation::JumpIfNotZero,
+    Operation::Multiply,
+    Operation::Output,
+    Operation::Halt,
+];
+
+impl Operation {
+    pub fn register_operand(&self) -> RegisterOperand {
+        match self {
+            Operation::Accumulate | Operation::Multiply | Operation::Output => RegisterOperand::Optional,
+            Operation::JumpIfZero | Operation::JumpIfNotZero => RegisterOperand::Required,
+            Operation::Jump | Operation::NoOperation | Operation::Halt => RegisterOperand::None,
+        }
+    }
+
+    pub fn takes_argument(&self) -> bool {
+        !matches!(self, Operation::Output | Operation::Halt)
+    }
+
+    // Jumps which go one way or the other depending on a register. No other operation lets a
+    // register decide where the instruction pointer goes next.
+    pub fn is_conditional(&self) -> bool {
+        matches!(self, Operation::JumpIfZero | Operation::JumpIfNotZero)
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "acc" => Ok(Operation::Accumulate),
+            "jmp" => Ok(Operation::Jump),
+            "nop" => Ok(Operation::NoOperation),
+            "jz" => Ok(Operation::JumpIfZero),
+            "jnz" => Ok(Operation::JumpIfNotZero),
+            "mul" => Ok(Operation::Multiply),
+            "out" => Ok(Operation::Output),
+            "hlt" => Ok(Operation::Halt),
+            _ => Err("Unknown Operation".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Operation::Accumulate => "acc",
+            Operation::Jump => "jmp",
+            Operation::NoOperation => "nop",
+            Operation::JumpIfZero => "jz",
+            Operation::JumpIfNotZero => "jnz",
+            Operation::Multiply => "mul",
+            Operation::Output => "out",
+            Operation::Halt => "hlt",
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use std::str::FromStr;
+use std::fmt;
+
+// `a` is the accumulator the original instruction set works on
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(Eq, Hash)]
+pub enum Register {
+    A,
+    B,
+    C,
+    D,
+}
+
+pub const REGISTERS: [Register; 4] = [Register::A, Register::B, Register::C, Register::D];
+
+impl Register {
+    pub fn index(&self) -> usize {
+        match self {
+            Register::A => 0,
+            Register::B => 1,
+            Register::C => 2,
+            Register::D => 3,
+        }
+    }
+}
+
+impl FromStr for Register {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "a" => Ok(Register::A),
+            "b" => Ok(Register::B),
+            "c" => Ok(Register::C),
+            "d" => Ok(Register::D),
+            _ => Err(format!("Unknown register '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Register::A => "a",
+            Register::B => "b",
+            Register::C => "c",
+            Register::D => "d",
+        })
+    }
+}
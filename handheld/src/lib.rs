@@ -0,0 +1,17 @@
+pub mod assembler;
+pub mod bytecode;
+pub mod cfg;
+pub mod debugger;
+pub mod emulator;
+pub mod fuzz;
+pub mod generator;
+pub mod instruction;
+pub mod repair;
+pub mod snapshot;
+pub mod trace;
+#[cfg(test)]
+mod test_support;
+
+pub use emulator::{Machine, RunOutcome, State};
+pub use instruction::Instruction;
+pub use instruction::operation::Operation;
@@ -0,0 +1,255 @@
+use std::collections::{HashMap, HashSet};
+use crate::emulator::{LoopDetector, Machine, RunOutcome};
+use crate::snapshot::MachineSnapshot;
+use crate::instruction::Instruction;
+use crate::instruction::operation::Operation;
+
+// Flipping the instruction at `index` between jmp and nop makes the program halt with
+// `value` in the accumulator
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Repair {
+    pub index: usize,
+    pub value: i32,
+}
+
+pub fn swap_op(s: &Instruction) -> Instruction {
+    Instruction {
+        op: match s.op {
+            Operation::Jump => Operation::NoOperation,
+            Operation::NoOperation => Operation::Jump,
+            other => other,
+        },
+        ..*s
+    }
+}
+
+fn is_swappable(instruction: &Instruction) -> bool {
+    matches!(instruction.op, Operation::Jump | Operation::NoOperation)
+}
+
+// Where the instruction pointer goes after `instruction` runs at `ip`, if it stays in
+// memory or moves to just past the end. Only valid for unconditional control flow.
+fn successor(ip: usize, instruction: &Instruction, len: usize) -> Option<usize> {
+    let offset = match instruction.op {
+        Operation::Jump => instruction.arg as i64,
+        Operation::Halt => return Some(len),
+        _ => 1,
+    };
+    let next = ip as i64 + offset;
+    if next < 0 || next > len as i64 { None } else { Some(next as usize) }
+}
+
+fn run_repaired(memory: &[Instruction], index: usize) -> Option<i32> {
+    let mut repaired = memory.to_vec();
+    repaired[index] = swap_op(&repaired[index]);
+    let mut machine = Machine::new(&repaired);
+    match machine.run(None) {
+        RunOutcome::Halted => Some(machine.state.value),
+        _ => None,
+    }
+}
+
+// Runs the program with the instruction at `index` flipped, carrying on from `snapshot`
+// taken just before that instruction first ran. The snapshot need not remember where the
+// program went before: a run that loops back there comes round to the flip again, and its
+// loop is spotted from then on.
+fn fork_repaired(memory: &[Instruction], index: usize, snapshot: MachineSnapshot) -> Option<i32> {
+    let mut repaired = memory.to_vec();
+    repaired[index] = swap_op(&repaired[index]);
+    let mut machine = Machine::restore(&repaired, snapshot).unwrap();
+    match machine.run(None) {
+        RunOutcome::Halted => Some(machine.state.value),
+        _ => None,
+    }
+}
+
+// Tries flipping every jmp and nop. O(n^2). Like `find_repair`, gives None for a program
+// that already halts.
+//
+// Everything the program does before first reaching the flipped instruction is the same with
+// or without the flip, so each flip is run from a snapshot taken there during a single run of
+// the original program, rather than from the start. Flipping an instruction the original never
+// reaches changes nothing. The original program, and each flip, gets the step limit
+// `Machine::run` would give it, and a program that runs into that gives None.
+pub fn brute_force_repair(memory: &[Instruction]) -> Option<Repair> {
+    let step_limit = LoopDetector::new(memory).step_limit().unwrap_or(usize::MAX);
+    let mut original = Machine::new(memory);
+    let mut forks: HashMap<usize, Option<i32>> = HashMap::new();
+    let outcome = loop {
+        if original.steps() >= step_limit {
+            return None;
+        }
+        let ip = original.state.instruction_pointer as usize;
+        if ip < memory.len() && is_swappable(&memory[ip]) && !forks.contains_key(&ip) {
+            // Only the accumulator is wanted from the fork, so the output is left behind too
+            let snapshot = MachineSnapshot {
+                program_length: memory.len(),
+                state: original.state,
+                output: Vec::new(),
+                steps: original.steps(),
+                visited: LoopDetector::new(memory),
+            };
+            forks.insert(ip, fork_repaired(memory, ip, snapshot));
+        }
+        match original.run(Some(1)) {
+            RunOutcome::StepLimitExceeded => continue,
+            outcome => break outcome,
+        }
+    };
+    if outcome == RunOutcome::Halted {
+        return None;
+    }
+    (0..memory.len())
+        .filter(|index| is_swappable(&memory[*index]))
+        .find_map(|index| forks.get(&index).copied().flatten().map(|value| Repair { index, value }))
+}
+
+// Finds the lowest-index single jmp/nop flip which makes a program halt, in O(n), for a
+// program that does not already halt. A program with jz or jnz has no single path to follow,
+// so for those this falls back to `brute_force_repair` and takes O(n^2).
+//
+// Every address has exactly one successor, so the addresses which lead to the end of the
+// program can be found by searching backwards from it. A program that does not halt only
+// runs the instructions on its path from 0, so the flip must be on that path, and must send
+// the instruction pointer into the set which leads to the end. It can never lead back to the
+// flipped instruction, as then the original program would have halted too.
+pub fn find_repair(memory: &[Instruction]) -> Option<Repair> {
+    if memory.iter().any(|instruction| matches!(instruction.op, Operation::JumpIfZero | Operation::JumpIfNotZero)) {
+        return brute_force_repair(memory);
+    }
+    let len = memory.len();
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); len + 1];
+    for (ip, instruction) in memory.iter().enumerate() {
+        if let Some(next) = successor(ip, instruction, len) {
+            predecessors[next].push(ip);
+        }
+    }
+    let mut terminates = vec![false; len + 1];
+    let mut stack = vec![len];
+    terminates[len] = true;
+    while let Some(ip) = stack.pop() {
+        for previous in &predecessors[ip] {
+            if !terminates[*previous] {
+                terminates[*previous] = true;
+                stack.push(*previous);
+            }
+        }
+    }
+
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut ip = 0;
+    let mut best: Option<usize> = None;
+    while ip < len && visited.insert(ip) && memory[ip].op != Operation::Halt {
+        let instruction = &memory[ip];
+        if is_swappable(instruction)
+            && successor(ip, &swap_op(instruction), len).is_some_and(|next| terminates[next])
+            && best.is_none_or(|best| ip < best) {
+            best = Some(ip);
+        }
+        match successor(ip, instruction, len) {
+            Some(next) => ip = next,
+            None => break,
+        }
+    }
+    if ip == len || memory[ip].op == Operation::Halt {
+        // Already halts, so there is nothing to repair
+        return None;
+    }
+    best.and_then(|index| run_repaired(memory, index).map(|value| Repair { index, value }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::{run_until_repeat_or_halt, DEFAULT_STEP_LIMIT};
+    use crate::instruction::parse_program;
+    use proptest::prelude::*;
+    use crate::test_support::EXAMPLE;
+
+    #[test]
+    fn test_example() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        assert_eq!(find_repair(&program), Some(Repair { index: 7, value: 8 }));
+        assert_eq!(brute_force_repair(&program), Some(Repair { index: 7, value: 8 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unrepairable() -> Result<(), String> {
+        let program = parse_program("jmp +0\nacc +1\njmp -1")?;
+        assert_eq!(find_repair(&program), None);
+        assert_eq!(brute_force_repair(&program), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_out_of_bounds_program() -> Result<(), String> {
+        let program = parse_program("acc +2\nnop +2\njmp -5\nacc +1")?;
+        assert_eq!(find_repair(&program), Some(Repair { index: 1, value: 3 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extended_operations() -> Result<(), String> {
+        let program = parse_program("acc b +2\nnop +2\njmp -2\nout b\nhlt\njmp -5")?;
+        assert_eq!(find_repair(&program), Some(Repair { index: 1, value: 0 }));
+        let conditional = parse_program("jz c +2\nhlt\njmp +0\nacc +1")?;
+        assert_eq!(find_repair(&conditional), Some(Repair { index: 2, value: 1 }));
+        assert_eq!(brute_force_repair(&conditional), Some(Repair { index: 2, value: 1 }));
+        // b never repeats within the step limit, so the jmp is never reached
+        let counting = parse_program("acc b +1\njnz b -1\njmp +0")?;
+        assert_eq!(find_repair(&counting), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_longer_than_default_step_limit() {
+        let mut program = vec![Instruction::new(Operation::NoOperation, 0); DEFAULT_STEP_LIMIT + 100];
+        program.push(Instruction::new(Operation::Jump, 0));
+        assert_eq!(find_repair(&program), Some(Repair { index: DEFAULT_STEP_LIMIT + 100, value: 0 }));
+    }
+
+    #[test]
+    fn test_already_halts() -> Result<(), String> {
+        let program = parse_program("nop +0\nacc +1")?;
+        assert_eq!(find_repair(&program), None);
+        assert_eq!(brute_force_repair(&program), None);
+        Ok(())
+    }
+
+    // The plain search, restarting the program for every flip, to check the others against
+    fn restarting_repair(program: &[Instruction]) -> Option<Repair> {
+        if run_until_repeat_or_halt(program).0 == RunOutcome::Halted {
+            return None;
+        }
+        (0..program.len())
+            .filter(|index| is_swappable(&program[*index]))
+            .find_map(|index| run_repaired(program, index).map(|value| Repair { index, value }))
+    }
+
+    fn arb_program() -> impl Strategy<Value=Vec<Instruction>> {
+        (1..40usize).prop_flat_map(|len| prop::collection::vec(
+            (
+                prop_oneof![Just(Operation::Accumulate), Just(Operation::Jump), Just(Operation::NoOperation)],
+                -(len as i32) - 2..len as i32 + 2,
+            ).prop_map(|(op, arg)| Instruction::new(op, arg)),
+            len,
+        ))
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+        #[test]
+        fn prop_agrees_with_brute_force(program in arb_program()) {
+            prop_assert_eq!(find_repair(&program), restarting_repair(&program));
+        }
+
+        #[test]
+        fn prop_forking_agrees_with_restarting(program in arb_program()) {
+            prop_assert_eq!(brute_force_repair(&program), restarting_repair(&program));
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use crate::emulator::{LoopDetector, State};
+
+// Everything needed to carry on running a machine later, from `Machine::snapshot`. The
+// program itself is not included, only its length as a sanity check on `Machine::restore`.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct MachineSnapshot {
+    pub program_length: usize,
+    // Registers, instruction pointer and whether the machine has halted
+    pub state: State,
+    pub output: Vec<i32>,
+    pub steps: usize,
+    // Where the machine has already been, for loop detection
+    pub visited: LoopDetector,
+}
+
+impl MachineSnapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(s: &str) -> Result<MachineSnapshot, String> {
+        serde_json::from_str(s).map_err(|e| e.to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::{Machine, RunOutcome};
+    use crate::instruction::parse_program;
+    use crate::test_support::EXAMPLE;
+
+    #[test]
+    fn test_resume() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(Some(3)), RunOutcome::StepLimitExceeded);
+        let snapshot = MachineSnapshot::from_json(&machine.snapshot().to_json())?;
+        assert_eq!(snapshot, machine.snapshot());
+        assert_eq!(snapshot.steps, 3);
+        assert_eq!(snapshot.state.instruction_pointer, 6);
+
+        // The restored machine remembers the addresses visited before the snapshot
+        let mut restored = Machine::restore(&program, snapshot)?;
+        assert_eq!(restored.run(None), RunOutcome::InfiniteLoop { at: 1 });
+        assert_eq!(machine.run(None), RunOutcome::InfiniteLoop { at: 1 });
+        assert_eq!(restored.state, machine.state);
+        assert_eq!(restored.steps(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_registers_and_output() -> Result<(), String> {
+        let program = parse_program("acc b +2\nout b\nacc b -1\njnz b -2\nhlt")?;
+        let mut machine = Machine::new(&program);
+        machine.run(Some(3));
+        let mut restored = Machine::restore(&program, MachineSnapshot::from_json(&machine.snapshot().to_json())?)?;
+        assert_eq!(restored.output, vec![2]);
+        assert_eq!(restored.run(None), RunOutcome::Halted);
+        assert_eq!(restored.output, vec![2, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_errors() -> Result<(), String> {
+        let program = parse_program(EXAMPLE)?;
+        let snapshot = Machine::new(&program).snapshot();
+        assert_eq!(
+            Machine::restore(&program[..4], snapshot.clone()).err().unwrap(),
+            "Snapshot was taken from a program of 9 instructions, not 4",
+        );
+        let mut far = snapshot;
+        far.state.instruction_pointer = 10;
+        assert_eq!(Machine::restore(&program, far).err().unwrap(), "Snapshot instruction pointer 10 is outside the program");
+        assert!(MachineSnapshot::from_json("{}").is_err());
+        Ok(())
+    }
+}
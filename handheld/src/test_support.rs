@@ -0,0 +1,29 @@
+use proptest::prelude::*;
+use crate::instruction::Instruction;
+use crate::instruction::operation::{RegisterOperand, OPERATIONS};
+use crate::instruction::register::{Register, REGISTERS};
+
+// The example boot code from the puzzle, which loops back to instruction 1 with 5 in the
+// accumulator, and halts with 8 once instruction 7 is flipped
+pub const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+// Any instruction with an argument from `args`. Operands an operation ignores are set to the
+// defaults that parsing or decoding it gives back.
+pub fn instruction(args: impl Strategy<Value = i32>) -> impl Strategy<Value = Instruction> {
+    (0..OPERATIONS.len(), 0..REGISTERS.len(), args).prop_map(|(op, register, arg)| {
+        let op = OPERATIONS[op];
+        Instruction {
+            op,
+            register: if op.register_operand() == RegisterOperand::None { Register::A } else { REGISTERS[register] },
+            arg: if op.takes_argument() { arg } else { 0 },
+        }
+    })
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::emulator::RunOutcome;
+use crate::instruction::Instruction;
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct TraceEntry {
+    pub ip: u32,
+    pub instruction: Instruction,
+    pub acc_before: i32,
+    pub acc_after: i32,
+}
+
+#[derive(Serialize)]
+struct JsonEntry {
+    step: usize,
+    ip: u32,
+    instruction: String,
+    acc_before: i32,
+    acc_after: i32,
+}
+
+// Every instruction executed by a machine, in order
+#[derive(Default)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    pub fn new() -> Trace {
+        Trace { entries: Vec::new() }
+    }
+
+    pub fn record(&mut self, entry: TraceEntry) {
+        self.entries.push(entry);
+    }
+
+    // How many times the instruction at each address ran
+    pub fn hit_counts(&self) -> BTreeMap<u32, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in &self.entries {
+            *counts.entry(entry.ip).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // The addresses run from the first visit of `at` until the end of the trace
+    pub fn loop_body(&self, at: u32) -> Vec<u32> {
+        self.entries
+            .iter()
+            .skip_while(|entry| entry.ip != at)
+            .map(|entry| entry.ip)
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,ip,instruction,acc_before,acc_after\n");
+        for (step, entry) in self.entries.iter().enumerate() {
+            csv += &format!("{},{},{},{},{}\n", step, entry.ip, entry.instruction, entry.acc_before, entry.acc_after);
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries: Vec<JsonEntry> = self.entries
+            .iter()
+            .enumerate()
+            .map(|(step, entry)| JsonEntry {
+                step,
+                ip: entry.ip,
+                instruction: entry.instruction.to_string(),
+                acc_before: entry.acc_before,
+                acc_after: entry.acc_after,
+            })
+            .collect();
+        serde_json::to_string_pretty(&entries).unwrap()
+    }
+
+    // Instructions ordered from most to least executed, plus the loop body if the run looped
+    pub fn summary(&self, outcome: RunOutcome) -> String {
+        let mut instructions: BTreeMap<u32, Instruction> = BTreeMap::new();
+        for entry in &self.entries {
+            instructions.insert(entry.ip, entry.instruction);
+        }
+        let mut hits: Vec<(u32, usize)> = self.hit_counts().into_iter().collect();
+        hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut summary = format!("{} steps, outcome {:?}\n", self.entries.len(), outcome);
+        if let RunOutcome::InfiniteLoop { at } = outcome {
+            let body = self.loop_body(at);
+            summary += &format!(
+                "loop body ({} instructions): {}\n",
+                body.len(),
+                body.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(" -> ")
+            );
+        }
+        summary += &format!("{:>6} {:>8}  instruction\n", "ip", "hits");
+        for (ip, count) in hits {
+            summary += &format!("{:>6} {:>8}  {}\n", ip, count, instructions[&ip]);
+        }
+        summary
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::Machine;
+    use crate::instruction::parse_program;
+    use crate::test_support::EXAMPLE;
+
+    fn traced_run(source: &str, step_limit: Option<usize>) -> (RunOutcome, Trace) {
+        let program = parse_program(source).unwrap();
+        let mut machine = Machine::new(&program).with_trace();
+        let outcome = match step_limit {
+            Some(limit) => machine.run_steps(limit),
+            None => machine.run(None),
+        };
+        (outcome, machine.take_trace().unwrap())
+    }
+
+    #[test]
+    fn test_records_every_instruction() {
+        let (_, trace) = traced_run(EXAMPLE, None);
+        let ips: Vec<u32> = trace.entries.iter().map(|entry| entry.ip).collect();
+        assert_eq!(ips, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(trace.entries[5], TraceEntry {
+            ip: 3,
+            instruction: "acc +3".parse().unwrap(),
+            acc_before: 2,
+            acc_after: 5,
+        });
+        assert_eq!(trace.loop_body(1), vec![1, 2, 6, 7, 3, 4]);
+    }
+
+    #[test]
+    fn test_hit_counts() {
+        let (outcome, trace) = traced_run(EXAMPLE, Some(20));
+        assert_eq!(outcome, RunOutcome::StepLimitExceeded);
+        assert_eq!(trace.entries.len(), 20);
+        let counts = trace.hit_counts();
+        assert_eq!(counts[&0], 1);
+        assert_eq!(counts[&1], 4);
+        assert_eq!(counts[&4], 3);
+        assert!(!counts.contains_key(&5));
+    }
+
+    #[test]
+    fn test_csv() {
+        let (_, trace) = traced_run("acc +1\nnop +0\nacc -3", None);
+        assert_eq!(
+            trace.to_csv(),
+            "step,ip,instruction,acc_before,acc_after\n0,0,acc +1,0,1\n1,1,nop +0,1,1\n2,2,acc -3,1,-2\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let (_, trace) = traced_run("acc +1\nnop +0\nacc -3", None);
+        let json: serde_json::Value = serde_json::from_str(&trace.to_json()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(json[2]["instruction"], "acc -3");
+        assert_eq!(json[2]["acc_after"], -2);
+    }
+
+    #[test]
+    fn test_summary() {
+        let (outcome, trace) = traced_run(EXAMPLE, None);
+        let summary = trace.summary(outcome);
+        assert!(summary.starts_with("7 steps, outcome InfiniteLoop { at: 1 }\nloop body (6 instructions): 1 -> 2 -> 6 -> 7 -> 3 -> 4\n"));
+        assert!(summary.contains("     7        1  jmp -4\n"));
+    }
+}
@@ -0,0 +1,195 @@
+use num_bigint::BigUint;
+
+// How far apart joltages may be for one to charge from the other, and how far above the
+// highest adapter the device is rated
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Hardware {
+    pub min_gap: u64,
+    pub max_gap: u64,
+    pub device_offset: u64,
+}
+
+impl Hardware {
+    pub fn new(min_gap: u64, max_gap: u64, device_offset: u64) -> Result<Hardware, String> {
+        if min_gap > max_gap {
+            return Err(format!("The minimum gap {} is larger than the maximum gap {}", min_gap, max_gap));
+        }
+        Ok(Hardware { min_gap, max_gap, device_offset })
+    }
+
+    pub fn can_step(&self, from: u64, to: u64) -> bool {
+        to.checked_sub(from).is_some_and(|gap| (self.min_gap..=self.max_gap).contains(&gap))
+    }
+}
+
+// The adapters from the puzzle: 1 to 3 jolts apart, with the device 3 above the highest
+impl Default for Hardware {
+    fn default() -> Hardware {
+        Hardware { min_gap: 1, max_gap: 3, device_offset: 3 }
+    }
+}
+
+// One adapter rating per line, skipping blank lines
+pub fn parse_adapters(s: &str) -> Result<Vec<u64>, String> {
+    s
+        .lines()
+        .zip(1..)
+        .filter(|(line, _)| !line.trim().is_empty())
+        .map(|(line, line_number)| line.trim().parse().map_err(|_| format!("line {}: invalid rating '{}'", line_number, line)))
+        .collect()
+}
+
+// Every joltage in order from the outlet at 0, through the adapters, to the device
+pub fn build_chain(adapters: &[u64], hardware: &Hardware) -> Result<Vec<u64>, String> {
+    let mut chain = adapters.to_vec();
+    chain.sort_unstable();
+    chain.insert(0, 0);
+    let highest = *chain.last().unwrap();
+    let device = highest.checked_add(hardware.device_offset).ok_or_else(|| {
+        format!("The device rating {} + {} is larger than {}", highest, hardware.device_offset, u64::MAX)
+    })?;
+    chain.push(device);
+    Ok(chain)
+}
+
+// The gap between each joltage in `chain` and the next, when every adapter is used
+pub fn differences(chain: &[u64], hardware: &Hardware) -> Result<Vec<u64>, String> {
+    chain
+        .windows(2)
+        .map(|pair| if hardware.can_step(pair[0], pair[1]) {
+            Ok(pair[1] - pair[0])
+        } else {
+            Err(format!(
+                "{} and {} are {} jolts apart, outside {}..={}",
+                pair[0], pair[1], pair[1] - pair[0], hardware.min_gap, hardware.max_gap,
+            ))
+        })
+        .collect()
+}
+
+// The number of ways to get from the first joltage in `chain` to the last, skipping any of
+// those in between, with each step within the hardware's gaps. `chain` must be sorted.
+//
+// Working back from the end, the ways from each joltage are the sum of the ways from every
+// joltage it can step to, which are all just after it. O(n) for a fixed maximum gap, with a
+// count that cannot overflow. Gaps are found by subtracting, which cannot overflow either.
+pub fn count_arrangements(chain: &[u64], hardware: &Hardware) -> BigUint {
+    if chain.is_empty() {
+        return BigUint::from(0u32);
+    }
+    let mut ways: Vec<BigUint> = vec![BigUint::from(0u32); chain.len()];
+    ways[chain.len() - 1] = BigUint::from(1u32);
+    for idx in (0..chain.len() - 1).rev() {
+        ways[idx] = (idx + 1..chain.len())
+            .skip_while(|next| chain[*next] - chain[idx] < hardware.min_gap)
+            .take_while(|next| chain[*next] - chain[idx] <= hardware.max_gap)
+            .map(|next| &ways[next])
+            .sum();
+    }
+    ways.swap_remove(0)
+}
+
+// Tries every arrangement one at a time, so only usable for short chains
+pub fn count_arrangements_naive(chain: &[u64], hardware: &Hardware) -> BigUint {
+    if chain.len() <= 1 {
+        return BigUint::from(chain.len() as u32);
+    }
+    (1..chain.len())
+        .filter(|next| hardware.can_step(chain[0], chain[*next]))
+        .map(|next| count_arrangements_naive(&chain[next..], hardware))
+        .sum()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const SMALL_EXAMPLE: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
+    const LARGE_EXAMPLE: &str = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3";
+
+    #[test]
+    fn test_examples() -> Result<(), String> {
+        let hardware = Hardware::default();
+        let small = build_chain(&parse_adapters(SMALL_EXAMPLE)?, &hardware)?;
+        assert_eq!(count_arrangements(&small, &hardware), BigUint::from(8u32));
+        assert_eq!(count_arrangements_naive(&small, &hardware), BigUint::from(8u32));
+        let large = build_chain(&parse_adapters(LARGE_EXAMPLE)?, &hardware)?;
+        assert_eq!(count_arrangements(&large, &hardware), BigUint::from(19208u32));
+        let gaps = differences(&large, &hardware)?;
+        assert_eq!(gaps.iter().filter(|gap| **gap == 1).count(), 22);
+        assert_eq!(gaps.iter().filter(|gap| **gap == 3).count(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_beyond_u64() -> Result<(), String> {
+        // Every adapter from 1 to 200 gives a tribonacci number of arrangements
+        let chain = build_chain(&(1..=200).collect::<Vec<u64>>(), &Hardware::default())?;
+        let (mut a, mut b, mut c) = (BigUint::from(0u32), BigUint::from(0u32), BigUint::from(1u32));
+        for _ in 0..200 {
+            let next = &a + &b + &c;
+            a = b;
+            b = c;
+            c = next;
+        }
+        assert!(c > BigUint::from(u64::MAX));
+        assert_eq!(count_arrangements(&chain, &Hardware::default()), c);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gap_too_wide() {
+        let hardware = Hardware::default();
+        assert_eq!(count_arrangements(&[0, 1, 5, 8], &hardware), BigUint::from(0u32));
+        assert_eq!(count_arrangements(&[], &hardware), BigUint::from(0u32));
+        assert_eq!(differences(&[0, 1, 5, 8], &hardware).err().unwrap(), "1 and 5 are 4 jolts apart, outside 1..=3");
+        assert_eq!(parse_adapters("1\nx").err().unwrap(), "line 2: invalid rating 'x'");
+        assert_eq!(parse_adapters("1\r\n\r\n 2 \n\n"), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_other_hardware() -> Result<(), String> {
+        let hardware = Hardware::new(2, 4, 5)?;
+        let chain = build_chain(&[2, 3, 4, 6], &hardware)?;
+        assert_eq!(chain, vec![0, 2, 3, 4, 6, 11]);
+        // 0 -> 2 or 3 or 4, then on to 6, and the device is 5 above that, too far
+        assert_eq!(count_arrangements(&chain, &hardware), BigUint::from(0u32));
+        let hardware = Hardware::new(2, 5, 5)?;
+        // 0-2-4-6, 0-3-6, 0-2-6, 0-4-6, each then jumping 5 to the device
+        assert_eq!(count_arrangements(&chain, &hardware), BigUint::from(4u32));
+        assert_eq!(Hardware::new(3, 1, 3).err().unwrap(), "The minimum gap 3 is larger than the maximum gap 1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_near_u64_max() -> Result<(), String> {
+        let hardware = Hardware::new(1, u64::MAX, 3)?;
+        assert_eq!(
+            build_chain(&[u64::MAX - 2], &hardware).err().unwrap(),
+            format!("The device rating {} + 3 is larger than {}", u64::MAX - 2, u64::MAX),
+        );
+        // Every step is within the maximum gap, so any subset of the adapters can be skipped
+        let chain = build_chain(&[1, u64::MAX - 4], &hardware)?;
+        assert_eq!(count_arrangements(&chain, &hardware), BigUint::from(4u32));
+        assert_eq!(count_arrangements_naive(&chain, &hardware), BigUint::from(4u32));
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_agrees_with_naive(
+            adapters in prop::collection::vec(1..40u64, 0..20),
+            min_gap in 0..3u64,
+            extra in 0..4u64,
+            device_offset in 0..6u64,
+        ) {
+            let hardware = Hardware::new(min_gap, min_gap + extra, device_offset).unwrap();
+            let chain = build_chain(&adapters, &hardware).unwrap();
+            prop_assert_eq!(count_arrangements(&chain, &hardware), count_arrangements_naive(&chain, &hardware));
+        }
+    }
+}
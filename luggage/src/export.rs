@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashSet};
+use serde::Serialize;
+use crate::graph::BagGraph;
+use crate::rule::AllRules;
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    color: &'a str,
+    count: u128,
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// A Graphviz digraph with an edge from each bag to every bag it directly holds, labelled
+// with the count. When `highlight` is given, that bag and everything inside it is colored.
+pub fn to_dot(rules: &AllRules, highlight: Option<&str>) -> Result<String, String> {
+    let highlighted: HashSet<String> = match highlight {
+        Some(color) => {
+            let graph = BagGraph::from_rules(rules);
+            let mut reachable: HashSet<String> = graph
+                .contained_by(color)?
+                .into_iter()
+                .map(|bag| graph.name(bag).to_string())
+                .collect();
+            reachable.insert(color.to_string());
+            reachable
+        }
+        None => HashSet::new(),
+    };
+    let mut dot = String::from("digraph luggage {\n");
+    for rule in &rules.data {
+        if highlighted.contains(&rule.bag_color) {
+            dot += &format!("    {} [style=filled, fillcolor=gold];\n", quote(&rule.bag_color));
+        } else {
+            dot += &format!("    {};\n", quote(&rule.bag_color));
+        }
+    }
+    for rule in &rules.data {
+        for (count, inner) in &rule.contents.data {
+            let style = if highlighted.contains(&rule.bag_color) { ", color=goldenrod, penwidth=2" } else { "" };
+            dot += &format!("    {} -> {} [label=\"{}\"{}];\n", quote(&rule.bag_color), quote(inner), count, style);
+        }
+    }
+    dot += "}\n";
+    Ok(dot)
+}
+
+// Each color mapped to the colors it directly holds, with counts
+pub fn to_json(rules: &AllRules) -> String {
+    let adjacency: BTreeMap<&str, Vec<JsonEdge>> = rules.data
+        .iter()
+        .map(|rule| (
+            rule.bag_color.as_str(),
+            rule.contents.data.iter().map(|(count, color)| JsonEdge { color, count: *count }).collect()
+        ))
+        .collect();
+    serde_json::to_string_pretty(&adjacency).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 faded blue bag.
+faded blue bags contain no other bags.";
+
+    #[test]
+    fn test_dot() -> Result<(), String> {
+        let dot = to_dot(&EXAMPLE.parse()?, None)?;
+        assert!(dot.starts_with("digraph luggage {\n"));
+        assert!(dot.contains("    \"light red\" -> \"muted yellow\" [label=\"2\"];\n"));
+        assert!(dot.contains("    \"faded blue\";\n"));
+        assert!(!dot.contains("fillcolor"));
+        assert_eq!(dot.matches(" -> ").count(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot_highlight() -> Result<(), String> {
+        let dot = to_dot(&EXAMPLE.parse()?, Some("muted yellow"))?;
+        assert!(dot.contains("    \"muted yellow\" [style=filled, fillcolor=gold];\n"));
+        assert!(dot.contains("    \"shiny gold\" [style=filled, fillcolor=gold];\n"));
+        assert!(dot.contains("    \"faded blue\" [style=filled, fillcolor=gold];\n"));
+        assert!(dot.contains("    \"light red\";\n"));
+        assert!(dot.contains("    \"muted yellow\" -> \"faded blue\" [label=\"9\", color=goldenrod, penwidth=2];\n"));
+        assert!(to_dot(&EXAMPLE.parse()?, Some("plaid purple")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() -> Result<(), String> {
+        let json: serde_json::Value = serde_json::from_str(&to_json(&EXAMPLE.parse()?)).unwrap();
+        assert_eq!(json["light red"][1]["color"], "muted yellow");
+        assert_eq!(json["light red"][1]["count"], 2);
+        assert_eq!(json["faded blue"].as_array().unwrap().len(), 0);
+        assert_eq!(json.as_object().unwrap().len(), 5);
+        Ok(())
+    }
+}
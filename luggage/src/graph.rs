@@ -0,0 +1,290 @@
+use std::collections::{HashMap, VecDeque};
+use crate::rule::{AllRules, Color};
+
+pub type BagId = usize;
+
+// The rules with every color interned to a BagId, and each "contains n" as a weighted edge
+pub struct BagGraph {
+    names: Vec<Color>,
+    ids: HashMap<Color, BagId>,
+    edges: Vec<Vec<(BagId, u128)>>,
+    // For each bag, the bags which directly hold it
+    reverse_edges: Vec<Vec<(BagId, u128)>>,
+}
+
+impl BagGraph {
+    pub fn from_rules(rules: &AllRules) -> BagGraph {
+        let mut graph = BagGraph {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            edges: Vec::new(),
+            reverse_edges: Vec::new(),
+        };
+        for rule in &rules.data {
+            let bag = graph.intern(&rule.bag_color);
+            for (count, inner) in &rule.contents.data {
+                let inner = graph.intern(inner);
+                graph.edges[bag].push((inner, *count));
+                graph.reverse_edges[inner].push((bag, *count));
+            }
+        }
+        graph
+    }
+
+    fn intern(&mut self, color: &str) -> BagId {
+        if let Some(id) = self.ids.get(color) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(color.to_string());
+        self.ids.insert(color.to_string(), id);
+        self.edges.push(Vec::new());
+        self.reverse_edges.push(Vec::new());
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn id(&self, color: &str) -> Option<BagId> {
+        self.ids.get(color).copied()
+    }
+
+    pub fn name(&self, bag: BagId) -> &str {
+        &self.names[bag]
+    }
+
+    pub fn contents(&self, bag: BagId) -> &[(BagId, u128)] {
+        &self.edges[bag]
+    }
+
+    // Post-order walk of everything reachable from `root`, visiting each bag once.
+    // Done with an explicit stack so deep nesting cannot overflow the call stack.
+    fn post_order(&self, root: BagId) -> Result<Vec<BagId>, String> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark { Unvisited, InProgress, Done }
+
+        let mut marks = vec![Mark::Unvisited; self.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![(root, 0)];
+        marks[root] = Mark::InProgress;
+        while let Some((bag, next_edge)) = stack.pop() {
+            if let Some((inner, _)) = self.edges[bag].get(next_edge) {
+                stack.push((bag, next_edge + 1));
+                match marks[*inner] {
+                    Mark::Unvisited => {
+                        marks[*inner] = Mark::InProgress;
+                        stack.push((*inner, 0));
+                    }
+                    Mark::InProgress => return Err(format!("{} bags eventually contain themselves", self.names[*inner])),
+                    Mark::Done => {}
+                }
+            } else {
+                marks[bag] = Mark::Done;
+                order.push(bag);
+            }
+        }
+        Ok(order)
+    }
+
+    // Total number of bags inside `color`, counting nested bags
+    pub fn total_contents(&self, color: &str) -> Result<u128, String> {
+        let root = self.lookup(color)?;
+        let mut totals: Vec<u128> = vec![0; self.len()];
+        for bag in self.post_order(root)? {
+            totals[bag] = self.edges[bag]
+                .iter()
+                .try_fold(0u128, |total, (inner, count)| {
+                    count
+                        .checked_mul(1 + totals[*inner])
+                        .and_then(|bags| total.checked_add(bags))
+                })
+                .ok_or(format!("Too many bags inside {} bags to count", self.names[bag]))?;
+        }
+        Ok(totals[root])
+    }
+
+    fn lookup(&self, color: &str) -> Result<BagId, String> {
+        self.id(color).ok_or(format!("No rule for {} bags", color))
+    }
+
+    // Breadth first search from `start`, returning each bag reached and the bag it was reached from
+    fn search(&self, start: BagId, edges: &[Vec<(BagId, u128)>]) -> Vec<Option<BagId>> {
+        let mut reached_from: Vec<Option<BagId>> = vec![None; self.len()];
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(bag) = queue.pop_front() {
+            for (next, _) in &edges[bag] {
+                if *next != start && reached_from[*next].is_none() {
+                    reached_from[*next] = Some(bag);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        reached_from
+    }
+
+    fn reached(reached_from: Vec<Option<BagId>>) -> Vec<BagId> {
+        reached_from
+            .iter()
+            .enumerate()
+            .filter(|(_, from)| from.is_some())
+            .map(|(bag, _)| bag)
+            .collect()
+    }
+
+    // Colors which eventually contain at least one `color` bag
+    pub fn containers(&self, color: &str) -> Result<Vec<BagId>, String> {
+        Ok(BagGraph::reached(self.search(self.lookup(color)?, &self.reverse_edges)))
+    }
+
+    // Colors which eventually end up inside a `color` bag
+    pub fn contained_by(&self, color: &str) -> Result<Vec<BagId>, String> {
+        Ok(BagGraph::reached(self.search(self.lookup(color)?, &self.edges)))
+    }
+
+    pub fn count_containing(&self, color: &str) -> Result<usize, String> {
+        Ok(self.containers(color)?.len())
+    }
+
+    // The shortest chain of bags from `from` down to `to`, and how many `to` bags are
+    // held along that chain by one `from` bag
+    pub fn path(&self, from: &str, to: &str) -> Result<Option<(Vec<BagId>, u128)>, String> {
+        let from = self.lookup(from)?;
+        let to = self.lookup(to)?;
+        if from == to {
+            return Ok(Some((vec![from], 1)));
+        }
+        let reached_from = self.search(from, &self.edges);
+        if reached_from[to].is_none() {
+            return Ok(None);
+        }
+        let mut chain = vec![to];
+        while let Some(previous) = reached_from[*chain.last().unwrap()] {
+            chain.push(previous);
+        }
+        chain.reverse();
+        let count = chain
+            .windows(2)
+            .try_fold(1u128, |total, pair| {
+                let (_, count) = self.edges[pair[0]].iter().find(|(inner, _)| *inner == pair[1]).unwrap();
+                total.checked_mul(*count)
+            })
+            .ok_or("Too many bags along the path to count".to_string())?;
+        Ok(Some((chain, count)))
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.";
+
+    const EXAMPLE_2: &str = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 2 dark orange bags.
+dark orange bags contain 2 dark yellow bags.
+dark yellow bags contain 2 dark green bags.
+dark green bags contain 2 dark blue bags.
+dark blue bags contain 2 dark violet bags.
+dark violet bags contain no other bags.";
+
+    fn graph(s: &str) -> BagGraph {
+        BagGraph::from_rules(&s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_example_contains() -> Result<(), String> {
+        assert_eq!(graph(EXAMPLE).count_containing("shiny gold")?, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_containers() -> Result<(), String> {
+        let g = graph(EXAMPLE);
+        let mut names: Vec<&str> = g.containers("shiny gold")?.into_iter().map(|bag| g.name(bag)).collect();
+        names.sort();
+        assert_eq!(names, vec!["bright white", "dark orange", "light red", "muted yellow"]);
+        assert!(g.containers("light red")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_contained_by() -> Result<(), String> {
+        let g = graph(EXAMPLE);
+        let mut names: Vec<&str> = g.contained_by("shiny gold")?.into_iter().map(|bag| g.name(bag)).collect();
+        names.sort();
+        assert_eq!(names, vec!["dark olive", "dotted black", "faded blue", "vibrant plum"]);
+        assert!(g.contained_by("faded blue")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_path() -> Result<(), String> {
+        let g = graph(EXAMPLE);
+        let (chain, count) = g.path("light red", "vibrant plum")?.unwrap();
+        let names: Vec<&str> = chain.into_iter().map(|bag| g.name(bag)).collect();
+        assert_eq!(names, vec!["light red", "bright white", "shiny gold", "vibrant plum"]);
+        assert_eq!(count, 2);
+        assert_eq!(g.path("dark orange", "faded blue")?.unwrap().1, 36);
+        assert_eq!(g.path("faded blue", "shiny gold")?, None);
+        assert_eq!(g.path("faded blue", "faded blue")?.unwrap().1, 1);
+        assert!(g.path("faded blue", "plaid purple").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_total_contents() -> Result<(), String> {
+        assert_eq!(graph(EXAMPLE).total_contents("shiny gold")?, 32);
+        assert_eq!(graph(EXAMPLE_2).total_contents("shiny gold")?, 126);
+        Ok(())
+    }
+
+    #[test]
+    fn test_weighted_edges() {
+        let g = graph(EXAMPLE);
+        let light_red = g.id("light red").unwrap();
+        assert_eq!(g.contents(light_red).len(), 2);
+        assert_eq!(g.contents(light_red).iter().map(|(_, count)| count).sum::<u128>(), 3);
+        assert_eq!(g.len(), 9);
+    }
+
+    #[test]
+    fn test_deep_nesting_does_not_blow_up() -> Result<(), String> {
+        // Every level holds 9 bags of the next, so the count is far past u64
+        let rules: String = (0..35)
+            .map(|level| format!("dark c{} bags contain 9 dark c{} bags.\n", level, level + 1))
+            .collect::<String>() + "dark c35 bags contain no other bags.";
+        let total = graph(&rules).total_contents("dark c0")?;
+        assert_eq!(total, (9u128.pow(36) - 9) / 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let rules: String = (0..50)
+            .map(|level| format!("dark c{} bags contain 999 dark c{} bags.\n", level, level + 1))
+            .collect::<String>() + "dark c50 bags contain no other bags.";
+        assert!(graph(&rules).total_contents("dark c0").is_err());
+    }
+
+    #[test]
+    fn test_unknown_color() {
+        assert!(graph(EXAMPLE).total_contents("plaid purple").is_err());
+    }
+}
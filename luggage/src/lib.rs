@@ -0,0 +1,4 @@
+pub mod export;
+pub mod graph;
+pub mod rule;
+pub mod validate;
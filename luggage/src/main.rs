@@ -0,0 +1,74 @@
+use clap::{Arg, App, ArgGroup};
+use std::fs;
+use std::process;
+use luggage::export::{to_dot, to_json};
+use luggage::graph::BagGraph;
+use luggage::rule::AllRules;
+use luggage::validate::validate;
+
+
+fn print_colors(graph: &BagGraph, bags: Vec<usize>) {
+    let mut names: Vec<&str> = bags.into_iter().map(|bag| graph.name(bag)).collect();
+    names.sort();
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+fn run(rules: &AllRules, args: &clap::ArgMatches) -> Result<(), String> {
+    let graph = &BagGraph::from_rules(rules);
+    if args.is_present("dot") {
+        print!("{}", to_dot(rules, args.value_of("highlight"))?);
+    } else if args.is_present("json") {
+        println!("{}", to_json(rules));
+    } else if let Some(color) = args.value_of("contains") {
+        print_colors(graph, graph.containers(color)?);
+    } else if let Some(color) = args.value_of("contained-by") {
+        print_colors(graph, graph.contained_by(color)?);
+    } else if let Some(color) = args.value_of("depth") {
+        println!("{}", graph.total_contents(color)?);
+    } else if let Some(mut colors) = args.values_of("path") {
+        let from = colors.next().unwrap();
+        let to = colors.next().unwrap();
+        let (chain, count) = graph.path(from, to)?.ok_or(format!("{} bags never contain {} bags", from, to))?;
+        println!("{}", chain.into_iter().map(|bag| graph.name(bag)).collect::<Vec<_>>().join(" -> "));
+        println!("{}", count);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = App::new("Query the luggage rules of AOC 2020 day seven")
+        .arg(Arg::with_name("input-file").takes_value(true).required(true))
+        .arg(Arg::with_name("contains").long("contains").value_name("color").takes_value(true)
+            .help("Every color which eventually contains a bag of this color"))
+        .arg(Arg::with_name("contained-by").long("contained-by").value_name("color").takes_value(true)
+            .help("Every color which eventually ends up inside a bag of this color"))
+        .arg(Arg::with_name("path").long("path").value_names(&["from", "to"]).number_of_values(2)
+            .help("The chain of bags from one color down to another, and how many of the last are inside the first"))
+        .arg(Arg::with_name("depth").long("depth").value_name("color").takes_value(true)
+            .help("How many bags in total are inside a bag of this color"))
+        .arg(Arg::with_name("dot").long("dot")
+            .help("Export the rules as a Graphviz DOT graph"))
+        .arg(Arg::with_name("highlight").long("highlight").value_name("color").takes_value(true).requires("dot")
+            .help("Highlight this color and every bag inside it in the DOT graph"))
+        .arg(Arg::with_name("json").long("json")
+            .help("Export the rules as a JSON adjacency list"))
+        .group(ArgGroup::with_name("query").args(&["contains", "contained-by", "path", "depth", "dot", "json"]).required(true))
+        .get_matches();
+    let all_rules = fs::read_to_string(
+        args.value_of("input-file").unwrap()
+    )
+        .unwrap()
+        .parse::<AllRules>().unwrap();
+    if let Err(errors) = validate(&all_rules) {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        process::exit(1);
+    }
+    if let Err(error) = run(&all_rules, &args) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,87 @@
+pub mod grammar;
+
+use std::str::FromStr;
+use crate::rule::grammar::parse_rule;
+
+pub type Color = String;
+
+pub struct ColorRule {
+    pub bag_color: Color,
+    pub contents: BagContents,
+}
+
+// Each entry is how many of a color of bag must be inside
+pub struct BagContents {
+    pub data: Vec<(u128, Color)>
+}
+
+// Rules are kept in file order, with data[idx] read from line lines[idx]
+pub struct AllRules {
+    pub data: Vec<ColorRule>,
+    pub lines: Vec<usize>,
+}
+
+impl AllRules {
+    pub fn rule(&self, color: &str) -> Option<&ColorRule> {
+        self.data.iter().find(|rule| rule.bag_color == color)
+    }
+}
+
+impl FromStr for ColorRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_rule(s)
+    }
+}
+
+impl FromStr for AllRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = AllRules { data: Vec::new(), lines: Vec::new() };
+        for (line, line_number) in s.lines().zip(1..) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            rules.data.push(line.parse().map_err(|e| format!("line {}, {}", line_number, e))?);
+            rules.lines.push(line_number);
+        }
+        Ok(rules)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_example_1() -> Result<(), String> {
+        let rule = "light red bags contain 1 bright white bag, 2 muted yellow bags.".parse::<ColorRule>()?;
+        assert_eq!(rule.bag_color, "light red");
+        assert_eq!(rule.contents.data, vec![(1, "bright white".to_string()), (2, "muted yellow".to_string())]);
+        Ok(())
+    }
+    #[test]
+    fn test_example_2() -> Result<(), String> {
+        let rule = "faded blue bags contain no other bags.".parse::<ColorRule>()?;
+        assert_eq!(rule.bag_color, "faded blue");
+        assert_eq!(rule.contents.data.len(), 0);
+        Ok(())
+    }
+    #[test]
+    fn test_all_rules() -> Result<(), String> {
+        let rules = "light red bags contain 1 bright white bag.\n\n  \nbright white bags contain no other bags.\n".parse::<AllRules>()?;
+        assert_eq!(rules.data.len(), 2);
+        assert_eq!(rules.lines, vec![1, 4]);
+        assert_eq!(rules.rule("bright white").unwrap().contents.data.len(), 0);
+        Ok(())
+    }
+    #[test]
+    fn test_all_rules_error_line() {
+        assert_eq!(
+            "faded blue bags contain no other bags.\nlight red bags contain 1 bright white.".parse::<AllRules>().err().unwrap(),
+            "line 2, column 38: expected 'bag' or 'bags', found '.'"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// Tokenizer and recursive descent parser for rule sentences:
+//
+//   rule     := color bag-word "contain" contents "."
+//   contents := "no" "other" "bags" | item ("," item)*
+//   item     := count color bag-word
+//   color    := word+
+//   bag-word := "bag" | "bags"
+//
+// Whitespace between tokens is ignored, and colors may be any number of words.
+use crate::rule::{BagContents, ColorRule};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum TokenKind {
+    Word,
+    Number,
+    Comma,
+    Period,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    // 1-based column of the first character
+    column: usize,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            ',' => TokenKind::Comma,
+            '.' => TokenKind::Period,
+            c if c.is_ascii_digit() => TokenKind::Number,
+            c if c.is_alphabetic() => TokenKind::Word,
+            c => return Err(format!("column {}: unexpected character {:?}", column(s, start), c)),
+        };
+        let mut end = start + c.len_utf8();
+        if kind == TokenKind::Number || kind == TokenKind::Word {
+            while let Some((idx, next)) = chars.peek() {
+                let continues = match kind {
+                    TokenKind::Number => next.is_ascii_digit(),
+                    _ => next.is_alphanumeric() || *next == '-',
+                };
+                if !continues {
+                    break;
+                }
+                end = idx + next.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(Token { kind, text: &s[start..end], column: column(s, start) });
+    }
+    Ok(tokens)
+}
+
+fn column(s: &str, byte_idx: usize) -> usize {
+    s[..byte_idx].chars().count() + 1
+}
+
+fn is_bag_word(token: &Token) -> bool {
+    token.kind == TokenKind::Word && (token.text == "bag" || token.text == "bags")
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    // Column just past the end of the input, for errors about missing tokens
+    end_column: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!("column {}: expected {}, found '{}'", token.column, expected, token.text),
+            None => format!("column {}: expected {}, found end of rule", self.end_column, expected),
+        }
+    }
+
+    fn next_if(&mut self, matches: impl Fn(&Token) -> bool) -> Option<Token<'a>> {
+        let token = *self.peek().filter(|token| matches(token))?;
+        self.position += 1;
+        Some(token)
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), String> {
+        self.next_if(|token| token.kind == TokenKind::Word && token.text == word)
+            .map(|_| ())
+            .ok_or_else(|| self.error(&format!("'{}'", word)))
+    }
+
+    fn expect_bag_word(&mut self) -> Result<(), String> {
+        self.next_if(is_bag_word)
+            .map(|_| ())
+            .ok_or_else(|| self.error("'bag' or 'bags'"))
+    }
+
+    fn color(&mut self) -> Result<String, String> {
+        let mut words = Vec::new();
+        while let Some(token) = self.next_if(|token| token.kind == TokenKind::Word && !is_bag_word(token)) {
+            words.push(token.text);
+        }
+        if words.is_empty() {
+            return Err(self.error("a color"));
+        }
+        Ok(words.join(" "))
+    }
+
+    fn count(&mut self) -> Result<u128, String> {
+        let token = self.next_if(|token| token.kind == TokenKind::Number)
+            .ok_or_else(|| self.error("a count or 'no other bags'"))?;
+        match token.text.parse::<u128>() {
+            Ok(0) => Err(format!("column {}: a bag cannot contain 0 bags of a color", token.column)),
+            Ok(count) => Ok(count),
+            Err(_) => Err(format!("column {}: count '{}' is too large", token.column, token.text)),
+        }
+    }
+
+    fn contents(&mut self) -> Result<BagContents, String> {
+        if self.next_if(|token| token.kind == TokenKind::Word && token.text == "no").is_some() {
+            self.expect_word("other")?;
+            self.expect_word("bags")?;
+            return Ok(BagContents { data: Vec::new() });
+        }
+        let mut data = Vec::new();
+        loop {
+            let count = self.count()?;
+            let color = self.color()?;
+            self.expect_bag_word()?;
+            data.push((count, color));
+            if self.next_if(|token| token.kind == TokenKind::Comma).is_none() {
+                return Ok(BagContents { data });
+            }
+        }
+    }
+
+    fn rule(&mut self) -> Result<ColorRule, String> {
+        let bag_color = self.color()?;
+        self.expect_bag_word()?;
+        self.expect_word("contain")?;
+        let contents = self.contents()?;
+        self.next_if(|token| token.kind == TokenKind::Period)
+            .ok_or_else(|| self.error("',' or '.'"))?;
+        if self.peek().is_some() {
+            return Err(self.error("end of rule"));
+        }
+        Ok(ColorRule { bag_color, contents })
+    }
+}
+
+pub fn parse_rule(s: &str) -> Result<ColorRule, String> {
+    Parser {
+        tokens: tokenize(s)?,
+        position: 0,
+        end_column: s.trim_end().chars().count() + 1,
+    }.rule()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(s: &str) -> Vec<(u128, String)> {
+        parse_rule(s).unwrap().contents.data
+    }
+
+    #[test]
+    fn test_tokenize() -> Result<(), String> {
+        let tokens = tokenize("  light red bags contain 12 dark-olive bags,1 x.")?;
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Word, TokenKind::Word, TokenKind::Word, TokenKind::Word, TokenKind::Number,
+            TokenKind::Word, TokenKind::Word, TokenKind::Comma, TokenKind::Number, TokenKind::Word,
+            TokenKind::Period,
+        ]);
+        assert_eq!(tokens[0].column, 3);
+        assert_eq!(tokens[4].text, "12");
+        assert_eq!(tokens[5].text, "dark-olive");
+        Ok(())
+    }
+
+    #[test]
+    fn test_arbitrary_length_colors() {
+        let rule = parse_rule("red bags contain 1 very light sea green bag, 3 dark-olive bags.").unwrap();
+        assert_eq!(rule.bag_color, "red");
+        assert_eq!(rule.contents.data, vec![(1, "very light sea green".to_string()), (3, "dark-olive".to_string())]);
+    }
+
+    #[test]
+    fn test_bag_and_bags() {
+        assert_eq!(contents("light red bag contain 2 muted yellow bag, 1 bright white bags."), vec![
+            (2, "muted yellow".to_string()),
+            (1, "bright white".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_stray_whitespace() {
+        let rule = parse_rule("  light   red bags\tcontain 1 bright  white bag ,2 muted yellow bags .  ").unwrap();
+        assert_eq!(rule.bag_color, "light red");
+        assert_eq!(rule.contents.data, vec![(1, "bright white".to_string()), (2, "muted yellow".to_string())]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse_rule("light red bags holds 1 bright white bag.").err().unwrap(),
+            "column 16: expected 'contain', found 'holds'"
+        );
+        assert_eq!(
+            parse_rule("light red bags contain some bright white bags.").err().unwrap(),
+            "column 24: expected a count or 'no other bags', found 'some'"
+        );
+        assert_eq!(
+            parse_rule("light red bags contain 1 bright white bag").err().unwrap(),
+            "column 42: expected ',' or '.', found end of rule"
+        );
+        assert_eq!(
+            parse_rule("light red bags contain 1 bag.").err().unwrap(),
+            "column 26: expected a color, found 'bag'"
+        );
+        assert_eq!(
+            parse_rule("light red bags contain 0 bright white bags.").err().unwrap(),
+            "column 24: a bag cannot contain 0 bags of a color"
+        );
+        assert_eq!(
+            parse_rule("light red bags contain no other bags. extra").err().unwrap(),
+            "column 39: expected end of rule, found 'extra'"
+        );
+        assert_eq!(
+            parse_rule("light red bags contain 1 bright white bag; 2 muted yellow bags.").err().unwrap(),
+            "column 42: unexpected character ';'"
+        );
+        assert_eq!(parse_rule("").err().unwrap(), "column 1: expected a color, found end of rule");
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::rule::{AllRules, ColorRule, Color};
+
+#[derive(PartialEq, Debug)]
+pub enum RuleError {
+    // The path starts and ends with the same color
+    Cycle(Vec<Color>),
+    DanglingReference { bag: Color, missing: Color },
+    DuplicateRule { color: Color, lines: Vec<usize> },
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Cycle(path) => write!(f, "cycle: {}", path.join(" -> ")),
+            RuleError::DanglingReference { bag, missing } => write!(f, "{} bags contain {} bags, which have no rule", bag, missing),
+            RuleError::DuplicateRule { color, lines } => write!(
+                f,
+                "{} bags have more than one rule, on lines {}",
+                color,
+                lines.iter().map(|line| line.to_string()).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+fn find_duplicates(rules: &AllRules) -> Vec<RuleError> {
+    let mut lines: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (rule, line) in rules.data.iter().zip(rules.lines.iter().copied()) {
+        lines.entry(&rule.bag_color).or_default().push(line);
+    }
+    rules.data
+        .iter()
+        .filter_map(|rule| lines.remove(rule.bag_color.as_str()).map(|lines| (rule, lines)))
+        .filter(|(_, lines)| lines.len() > 1)
+        .map(|(rule, lines)| RuleError::DuplicateRule { color: rule.bag_color.clone(), lines })
+        .collect()
+}
+
+fn find_dangling(rules: &AllRules, by_color: &HashMap<&str, &ColorRule>) -> Vec<RuleError> {
+    rules.data
+        .iter()
+        .flat_map(|rule| rule.contents.data
+            .iter()
+            .filter(|(_, inner)| !by_color.contains_key(inner.as_str()))
+            .map(move |(_, inner)| RuleError::DanglingReference { bag: rule.bag_color.clone(), missing: inner.clone() })
+        )
+        .collect()
+}
+
+// Depth first search from every rule, reporting each edge which leads back onto the current path
+fn find_cycles(rules: &AllRules, by_color: &HashMap<&str, &ColorRule>) -> Vec<RuleError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark { InProgress, Done }
+
+    let mut marks: HashMap<&str, Mark> = HashMap::new();
+    let mut cycles = Vec::new();
+    for start in &rules.data {
+        if marks.contains_key(start.bag_color.as_str()) {
+            continue;
+        }
+        marks.insert(&start.bag_color, Mark::InProgress);
+        let mut stack: Vec<(&ColorRule, usize)> = vec![(start, 0)];
+        while let Some((rule, next_edge)) = stack.pop() {
+            match rule.contents.data.get(next_edge) {
+                Some((_, inner)) => {
+                    stack.push((rule, next_edge + 1));
+                    let inner_rule = match by_color.get(inner.as_str()) {
+                        Some(inner_rule) => inner_rule,
+                        None => continue,
+                    };
+                    match marks.get(inner.as_str()) {
+                        None => {
+                            marks.insert(inner, Mark::InProgress);
+                            stack.push((inner_rule, 0));
+                        }
+                        Some(Mark::InProgress) => {
+                            let mut path: Vec<Color> = stack
+                                .iter()
+                                .map(|(on_path, _)| on_path.bag_color.clone())
+                                .skip_while(|color| color != inner)
+                                .collect();
+                            path.push(inner.clone());
+                            cycles.push(RuleError::Cycle(path));
+                        }
+                        Some(Mark::Done) => {}
+                    }
+                }
+                None => {
+                    marks.insert(&rule.bag_color, Mark::Done);
+                }
+            }
+        }
+    }
+    cycles
+}
+
+// Checks the rules can be queried safely: every color has exactly one rule and no bag
+// can end up inside itself
+pub fn validate(rules: &AllRules) -> Result<(), Vec<RuleError>> {
+    let by_color: HashMap<&str, &ColorRule> = rules.data
+        .iter()
+        .map(|rule| (rule.bag_color.as_str(), rule))
+        .collect();
+    let mut errors = find_duplicates(rules);
+    errors.extend(find_dangling(rules, &by_color));
+    errors.extend(find_cycles(rules, &by_color));
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(s: &str) -> Vec<RuleError> {
+        validate(&s.parse().unwrap()).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(errors("light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 muted yellow bag.
+muted yellow bags contain no other bags."), vec![]);
+    }
+
+    #[test]
+    fn test_self_cycle() {
+        assert_eq!(
+            errors("light red bags contain 1 light red bag."),
+            vec![RuleError::Cycle(vec!["light red".to_string(), "light red".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_indirect_cycle() {
+        let found = errors("light red bags contain 1 bright white bag.
+dark orange bags contain 1 light red bag.
+bright white bags contain 2 muted yellow bags, 1 faded blue bag.
+faded blue bags contain no other bags.
+muted yellow bags contain 3 dark orange bags.");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].to_string(), "cycle: light red -> bright white -> muted yellow -> dark orange -> light red");
+    }
+
+    #[test]
+    fn test_dangling_reference() {
+        assert_eq!(
+            errors("light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain no other bags."),
+            vec![RuleError::DanglingReference { bag: "light red".to_string(), missing: "muted yellow".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_rule() {
+        let found = errors("light red bags contain 1 bright white bag.
+bright white bags contain no other bags.
+light red bags contain 2 bright white bags.");
+        assert_eq!(found, vec![RuleError::DuplicateRule { color: "light red".to_string(), lines: vec![1, 3] }]);
+        assert_eq!(found[0].to_string(), "light red bags have more than one rule, on lines 1, 3");
+    }
+
+    #[test]
+    fn test_deep_chain_does_not_overflow() {
+        let rules: String = (0..100_000)
+            .map(|level| format!("dark c{} bags contain 1 dark c{} bag.\n", level, level + 1))
+            .collect::<String>() + "dark c100000 bags contain 1 dark c0 bag.";
+        let found = errors(&rules);
+        assert_eq!(found.len(), 1);
+        match &found[0] {
+            RuleError::Cycle(path) => assert_eq!(path.len(), 100_002),
+            _ => panic!("expected a cycle"),
+        }
+    }
+}
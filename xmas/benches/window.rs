@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use proptest::prelude::Rng;
+use proptest::test_runner::{RngAlgorithm, TestRng};
+use xmas::window::{invalid_numbers, is_valid_naive};
+
+const NUMBER_COUNT: usize = 5_000;
+
+fn naive_invalid_numbers(data: &[u64], preamble_size: usize) -> Vec<(usize, u64)> {
+    (preamble_size..data.len())
+        .filter(|idx| !is_valid_naive(&data[idx - preamble_size..*idx], data[*idx]))
+        .map(|idx| (idx, data[idx]))
+        .collect()
+}
+
+fn bench_window(c: &mut Criterion) {
+    // The same seeded numbers on every run, wide enough that most are invalid, so the naive
+    // check rarely stops early
+    let mut rng = TestRng::deterministic_rng(RngAlgorithm::ChaCha);
+    let data: Vec<u64> = (0..NUMBER_COUNT).map(|_| rng.next_u64() % 1_000_000_000).collect();
+
+    let mut group = c.benchmark_group("validate");
+    group.sample_size(10);
+    for preamble_size in [25, 1000] {
+        group.bench_with_input(BenchmarkId::new("naive", preamble_size), &preamble_size, |b, size| b.iter(|| naive_invalid_numbers(&data, *size).len()));
+        group.bench_with_input(BenchmarkId::new("window", preamble_size), &preamble_size, |b, size| b.iter(|| invalid_numbers(&data, *size).len()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_window);
+criterion_main!(benches);
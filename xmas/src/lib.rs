@@ -0,0 +1,22 @@
+pub mod range;
+pub mod stream;
+pub mod window;
+
+// One number per line, skipping blank lines
+pub fn parse_numbers(s: &str) -> Result<Vec<u64>, String> {
+    s
+        .lines()
+        .zip(1..)
+        .filter(|(line, _)| !line.trim().is_empty())
+        .map(|(line, line_number)| line.trim().parse().map_err(|_| format!("line {}: invalid number '{}'", line_number, line)))
+        .collect()
+}
+
+// A preamble size from the command line, which must be at least one
+pub fn parse_preamble_size(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(0) => Err("The preamble size must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(_) => Err(format!("Invalid preamble size '{}'", s)),
+    }
+}
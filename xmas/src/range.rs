@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+// The contiguous range of at least two numbers which adds up to `target` and ends first, taking
+// the longest if several end at the same place. Numbers are never negative, so a window only
+// ever needs to grow at the end and shrink at the start, which makes this O(n). The sum is
+// kept as a u128, which no slice of u64s can overflow.
+pub fn find_contiguous_sum(data: &[u64], target: u64) -> Option<Range<usize>> {
+    let target = target as u128;
+    let mut start = 0;
+    let mut sum: u128 = 0;
+    for end in 0..data.len() {
+        sum += data[end] as u128;
+        while sum > target {
+            sum -= data[start] as u128;
+            start += 1;
+        }
+        if sum == target && end > start {
+            return Some(start..end + 1);
+        }
+    }
+    None
+}
+
+// Every contiguous range of at least two numbers which adds up to `target`, ordered by where
+// they end and then where they start. O(n) plus the number of ranges, using prefix sums: a
+// range adds up to `target` when the sums before its start and through its end differ by it.
+pub fn all_contiguous_sums(data: &[u64], target: u64) -> Vec<Range<usize>> {
+    let mut starts: HashMap<u128, Vec<usize>> = HashMap::new();
+    let mut ranges = Vec::new();
+    // prefixes[i] is the sum of the first i numbers, as a u128 so it cannot overflow
+    let mut prefixes: Vec<u128> = vec![0];
+    for (end, number) in data.iter().enumerate() {
+        // A range ending here needs two numbers, so it can start at end - 1 at the latest
+        if end >= 1 {
+            starts.entry(prefixes[end - 1]).or_default().push(end - 1);
+        }
+        prefixes.push(prefixes[end] + *number as u128);
+        if let Some(matching) = prefixes[end + 1].checked_sub(target as u128).and_then(|before| starts.get(&before)) {
+            ranges.extend(matching.iter().map(|start| *start..end + 1));
+        }
+    }
+    ranges
+}
+
+// The smallest and largest numbers in `range` added together, which can be too big for a u64
+pub fn encryption_weakness(data: &[u64], range: Range<usize>) -> u128 {
+    let range = &data[range];
+    *range.iter().min().unwrap() as u128 + *range.iter().max().unwrap() as u128
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const EXAMPLE: [u64; 20] = [35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576];
+
+    #[test]
+    fn test_example() {
+        assert_eq!(find_contiguous_sum(&EXAMPLE, 127), Some(2..6));
+        assert_eq!(all_contiguous_sums(&EXAMPLE, 127), vec![2..6]);
+        assert_eq!(encryption_weakness(&EXAMPLE, 2..6), 62);
+    }
+
+    #[test]
+    fn test_minimum_length_two() {
+        // 127 on its own does not count
+        assert_eq!(find_contiguous_sum(&[127, 1], 127), None);
+        assert_eq!(find_contiguous_sum(&[127, 0], 127), Some(0..2));
+        assert_eq!(find_contiguous_sum(&[], 0), None);
+        assert_eq!(find_contiguous_sum(&[0, 0], 0), Some(0..2));
+        assert_eq!(all_contiguous_sums(&[3, 127, 2], 127), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn test_all_ranges() {
+        let data = [1, 2, 3, 0, 3, 3];
+        assert_eq!(all_contiguous_sums(&data, 6), vec![0..3, 0..4, 2..5, 3..6, 4..6]);
+        assert_eq!(find_contiguous_sum(&data, 6), Some(0..3));
+    }
+
+    #[test]
+    fn test_sums_beyond_u64() {
+        let data = [u64::MAX, 1, u64::MAX - 1, 1];
+        assert_eq!(find_contiguous_sum(&data, u64::MAX), Some(1..3));
+        assert_eq!(all_contiguous_sums(&data, u64::MAX), vec![1..3, 2..4]);
+        assert_eq!(encryption_weakness(&data, 0..2), u64::MAX as u128 + 1);
+    }
+
+    fn naive(data: &[u64], target: u64) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        for end in 2..=data.len() {
+            for start in 0..end - 1 {
+                if data[start..end].iter().sum::<u64>() == target {
+                    ranges.push(start..end);
+                }
+            }
+        }
+        ranges
+    }
+
+    proptest! {
+        #[test]
+        fn prop_agrees_with_naive(data in prop::collection::vec(0..20u64, 0..60), target in 0..80u64) {
+            let expected = naive(&data, target);
+            prop_assert_eq!(all_contiguous_sums(&data, target), expected.clone());
+            // The first range to end is the longest of those ending there
+            let first = expected.first().map(|first| expected.iter().filter(|range| range.end == first.end).min_by_key(|range| range.start).unwrap().clone());
+            prop_assert_eq!(find_contiguous_sum(&data, target), first);
+        }
+    }
+}
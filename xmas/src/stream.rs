@@ -0,0 +1,71 @@
+use std::io::{BufRead, Lines};
+use crate::window::Validator;
+
+// Every invalid number read from `reader`, one number per line, with its line number counting
+// from 1, found as the numbers are read. Blank lines are skipped. Only the window is kept, so
+// `reader` can go on forever.
+pub struct InvalidNumbers<R> {
+    lines: Lines<R>,
+    line_number: usize,
+    validator: Validator,
+}
+
+impl<R: BufRead> InvalidNumbers<R> {
+    pub fn new(reader: R, preamble_size: usize) -> InvalidNumbers<R> {
+        InvalidNumbers { lines: reader.lines(), line_number: 0, validator: Validator::new(preamble_size) }
+    }
+}
+
+impl<R: BufRead> Iterator for InvalidNumbers<R> {
+    type Item = Result<(usize, u64), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in &mut self.lines {
+            self.line_number += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(format!("line {}: {}", self.line_number, e))),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let number: u64 = match line.trim().parse() {
+                Ok(number) => number,
+                Err(_) => return Some(Err(format!("line {}: invalid number '{}'", self.line_number, line))),
+            };
+            if self.validator.check(number).is_some() {
+                return Some(Ok((self.line_number, number)));
+            }
+        }
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_example() {
+        let input = "35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n182\n127\n219\n299\n277\n309\n576\n1\n";
+        let invalid: Vec<_> = InvalidNumbers::new(Cursor::new(input), 5).collect();
+        assert_eq!(invalid, vec![Ok((15, 127)), Ok((21, 1))]);
+    }
+
+    #[test]
+    fn test_skips_blank_lines() {
+        let input = "1\r\n2\r\n\r\n  \r\n3\r\n7\r\n\r\n";
+        let invalid: Vec<_> = InvalidNumbers::new(Cursor::new(input), 2).collect();
+        assert_eq!(invalid, vec![Ok((6, 7))]);
+    }
+
+    #[test]
+    fn test_reports_errors_and_carries_on() {
+        let mut invalid = InvalidNumbers::new(Cursor::new("1\n2\nx\n3\n7\n"), 2);
+        assert_eq!(invalid.next(), Some(Err("line 3: invalid number 'x'".to_string())));
+        assert_eq!(invalid.next(), Some(Ok((5, 7))));
+        assert_eq!(invalid.next(), None);
+    }
+}
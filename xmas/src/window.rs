@@ -0,0 +1,154 @@
+use std::collections::{HashMap, VecDeque};
+
+// The last `size` numbers seen, with how many times each value appears among them, so a new
+// number can be checked against every pair in O(w) by looking up each one's complement
+pub struct Window {
+    size: usize,
+    numbers: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
+}
+
+impl Window {
+    // An empty window would always be full, with no pairs in it, so every number would be invalid
+    pub fn new(size: usize) -> Window {
+        assert!(size > 0, "The window must hold at least one number");
+        Window { size, numbers: VecDeque::with_capacity(size + 1), counts: HashMap::with_capacity(size) }
+    }
+
+    // Adds `number`, dropping the oldest number once the window is full
+    pub fn push(&mut self, number: u64) {
+        self.numbers.push_back(number);
+        *self.counts.entry(number).or_insert(0) += 1;
+        if self.numbers.len() > self.size {
+            let oldest = self.numbers.pop_front().unwrap();
+            let count = self.counts.get_mut(&oldest).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.numbers.len() == self.size
+    }
+
+    // Whether `target` is the sum of two different numbers in the window. Two copies of the
+    // same value do not count as different numbers.
+    pub fn is_valid(&self, target: u64) -> bool {
+        self.counts.keys().any(|number| {
+            target.checked_sub(*number).is_some_and(|complement| complement != *number && self.counts.contains_key(&complement))
+        })
+    }
+}
+
+// The quadratic check the window replaces, kept to test and benchmark against
+pub fn is_valid_naive(chunk: &[u64], target: u64) -> bool {
+    for idx_a in 0..chunk.len() {
+        for idx_b in idx_a + 1..chunk.len() {
+            if chunk[idx_a] != chunk[idx_b] && chunk[idx_a] + chunk[idx_b] == target {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Checks numbers one at a time as they arrive, keeping only the last `preamble_size` of them
+pub struct Validator {
+    window: Window,
+    position: usize,
+}
+
+impl Validator {
+    pub fn new(preamble_size: usize) -> Validator {
+        Validator { window: Window::new(preamble_size), position: 0 }
+    }
+
+    // Takes the next number, returning its position if it is not the sum of two different
+    // numbers in the window. Numbers in the preamble are always valid.
+    pub fn check(&mut self, number: u64) -> Option<usize> {
+        let invalid = self.window.is_full() && !self.window.is_valid(number);
+        self.window.push(number);
+        self.position += 1;
+        if invalid { Some(self.position - 1) } else { None }
+    }
+}
+
+// The position and value of every number after the preamble which is not the sum of two
+// different numbers among the `preamble_size` before it
+pub fn invalid_numbers(data: &[u64], preamble_size: usize) -> Vec<(usize, u64)> {
+    let mut validator = Validator::new(preamble_size);
+    data.iter().filter_map(|number| validator.check(*number).map(|position| (position, *number))).collect()
+}
+
+pub fn first_invalid(data: &[u64], preamble_size: usize) -> Option<(usize, u64)> {
+    let mut validator = Validator::new(preamble_size);
+    data.iter().find_map(|number| validator.check(*number).map(|position| (position, *number)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const EXAMPLE: [u64; 20] = [35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576];
+
+    #[test]
+    fn test_example() {
+        assert_eq!(first_invalid(&EXAMPLE, 5), Some((14, 127)));
+        assert_eq!(invalid_numbers(&EXAMPLE, 5), vec![(14, 127)]);
+    }
+
+    #[test]
+    fn test_twenty_five_preamble() {
+        let mut window = Window::new(25);
+        (1..=25).for_each(|number| window.push(number));
+        // 26 and 49 are valid, 100 has no pair, and 50 would need 25 twice
+        assert!(window.is_valid(26));
+        assert!(window.is_valid(49));
+        assert!(!window.is_valid(100));
+        assert!(!window.is_valid(50));
+    }
+
+    #[test]
+    fn test_pair_must_be_different() {
+        assert_eq!(first_invalid(&[5, 1, 10], 2), Some((2, 10)));
+        assert_eq!(first_invalid(&[5, 5, 10], 2), Some((2, 10)));
+        assert_eq!(first_invalid(&[5, 1, 6, 7], 2), None);
+    }
+
+    #[test]
+    fn test_duplicates_leave_the_window_one_at_a_time() {
+        let mut window = Window::new(3);
+        for number in [4, 4, 1, 4] {
+            window.push(number);
+        }
+        // The window is [4, 1, 4], so one 4 is still there
+        assert!(window.is_valid(5));
+        window.push(2);
+        window.push(2);
+        // The window is [4, 2, 2]
+        assert!(window.is_valid(6));
+        assert!(!window.is_valid(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "The window must hold at least one number")]
+    fn test_empty_window() {
+        Window::new(0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_agrees_with_naive(data in prop::collection::vec(0..50u64, 0..200), preamble_size in 1..10usize) {
+            let naive: Vec<(usize, u64)> = (preamble_size..data.len())
+                .filter(|idx| !is_valid_naive(&data[idx - preamble_size..*idx], data[*idx]))
+                .map(|idx| (idx, data[idx]))
+                .collect();
+            prop_assert_eq!(invalid_numbers(&data, preamble_size), naive.clone());
+            prop_assert_eq!(first_invalid(&data, preamble_size), naive.first().copied());
+        }
+    }
+}